@@ -0,0 +1,173 @@
+use crate::days::answer::Answer;
+use crate::days::registry::Day;
+use crate::error::{Error, Result, WithPath};
+use crate::input::Input;
+use crate::runner::{self, format_duration};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::time::{Duration, Instant};
+
+/// A single timed run of one part, with parsing timed separately from solving
+pub struct Sample {
+    pub answer: Answer,
+    pub parse: Duration,
+    pub solve: Duration,
+}
+
+/// Parse `input` and solve `part` of `day` from it, timing each step
+pub fn sample(day: &Day, input: &Input, part: usize) -> Result<Sample> {
+    let start = Instant::now();
+    let parsed = (day.parse)(input)?;
+    let parse = start.elapsed();
+
+    let start = Instant::now();
+    let answer = (day.solve[part - 1])(input, parsed.as_ref())?;
+    let solve = start.elapsed();
+
+    Ok(Sample {
+        answer,
+        parse,
+        solve,
+    })
+}
+
+/// Durations are stored as whole nanoseconds so that baselines stay readable
+mod nanos {
+    use serde::{Deserialize, Deserializer, Serializer};
+    use std::time::Duration;
+
+    pub fn serialize<S: Serializer>(d: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u64(d.as_nanos() as u64)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+        Ok(Duration::from_nanos(u64::deserialize(deserializer)?))
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct Stats {
+    #[serde(with = "nanos")]
+    pub min: Duration,
+    #[serde(with = "nanos")]
+    pub median: Duration,
+    #[serde(with = "nanos")]
+    pub max: Duration,
+}
+
+impl Stats {
+    fn of(mut durations: Vec<Duration>) -> Self {
+        durations.sort();
+        Self {
+            min: durations[0],
+            median: durations[durations.len() / 2],
+            max: durations[durations.len() - 1],
+        }
+    }
+}
+
+/// The timings of running one part of a day a number of times
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Bench {
+    pub day: u32,
+    pub part: usize,
+    pub runs: usize,
+    pub answer: String,
+    pub parse: Stats,
+    pub solve: Stats,
+}
+
+/// Run `part` of `day` against `input` `runs` times. Fails on the first run that
+/// errors or panics.
+pub fn bench_part(day: &Day, part: usize, input: &Input, runs: usize) -> std::result::Result<Bench, String> {
+    let mut samples = vec![];
+
+    for _ in 0..runs.max(1) {
+        samples.push(runner::catch(|| sample(day, input, part))?);
+    }
+
+    Ok(Bench {
+        day: day.number,
+        part,
+        runs: samples.len(),
+        answer: samples[0].answer.to_string(),
+        parse: Stats::of(samples.iter().map(|s| s.parse).collect()),
+        solve: Stats::of(samples.iter().map(|s| s.solve).collect()),
+    })
+}
+
+pub fn print_bench(bench: &Bench) {
+    println!(
+        "Day {} part {}: {} ({} runs)",
+        bench.day, bench.part, bench.answer, bench.runs
+    );
+    for (name, stats) in [("parse", &bench.parse), ("solve", &bench.solve)] {
+        println!(
+            "    {}  min {:>10}  median {:>10}  max {:>10}",
+            name,
+            format_duration(stats.min),
+            format_duration(stats.median),
+            format_duration(stats.max),
+        );
+    }
+}
+
+pub fn save(path: &str, benches: &[Bench]) -> Result<()> {
+    let json = serde_json::to_string_pretty(benches).expect("benches always serialize");
+    fs::write(path, json + "\n").with_path(path)
+}
+
+pub fn load(path: &str) -> Result<Vec<Bench>> {
+    let json = fs::read_to_string(path).with_path(path)?;
+    serde_json::from_str(&json)
+        .map_err(|e| Error::parse(path, e.line(), e.column(), "", format!("invalid baseline: {}", e)))
+}
+
+/// A step of a part whose median got slower than the baseline allows
+pub struct Regression {
+    pub day: u32,
+    pub part: usize,
+    pub step: &'static str,
+    pub before: Duration,
+    pub after: Duration,
+}
+
+impl Regression {
+    /// How much slower the step got, as a percentage of the baseline
+    pub fn slowdown(&self) -> f64 {
+        (self.after.as_secs_f64() / self.before.as_secs_f64() - 1.0) * 100.0
+    }
+}
+
+/// Compare the medians of `current` with the same day and part in `baseline`. A step
+/// regressed when its median is more than `threshold` percent slower. Parts that
+/// aren't in the baseline are not compared.
+pub fn regressions(current: &[Bench], baseline: &[Bench], threshold: f64) -> Vec<Regression> {
+    let mut regressions = vec![];
+
+    for bench in current {
+        let Some(base) = baseline
+            .iter()
+            .find(|b| b.day == bench.day && b.part == bench.part)
+        else {
+            continue;
+        };
+
+        for (step, before, after) in [
+            ("parse", base.parse.median, bench.parse.median),
+            ("solve", base.solve.median, bench.solve.median),
+        ] {
+            if after.as_secs_f64() > before.as_secs_f64() * (1.0 + threshold / 100.0) {
+                regressions.push(Regression {
+                    day: bench.day,
+                    part: bench.part,
+                    step,
+                    before,
+                    after,
+                });
+            }
+        }
+    }
+
+    regressions
+}
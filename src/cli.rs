@@ -0,0 +1,628 @@
+use aoc2023::days::day3::{GearRules, Query};
+use aoc2023::days::day4::Scoring;
+use aoc2023::days::registry::DAYS;
+use aoc2023::input::Input;
+use std::ops::RangeInclusive;
+use std::str::FromStr;
+
+pub(crate) const USAGE: &str = "usage: cargo run -- <days> [options] [file]
+       cargo run -- all [--format <text|json>]
+       cargo run -- verify [days]
+       cargo run -- bench <days> [options] [file]
+       cargo run -- crosscheck <days> [options] [file]
+       cargo run -- generate <day> [--size <n>] [--seed <n>]
+       cargo run -- difftest <days> [--runs <n>] [--size <n>] [--seed <n>] [file]
+       cargo run -- list
+
+<days> is a single day like `5` or a range like `1..=16`. When no file is
+given each day reads inputs/day<N>/data.txt, and a file of `-` reads stdin.
+
+verify runs every input that has a matching inputs/day<N>/<name>.expected
+file and compares the answers, exiting nonzero on any mismatch.
+
+bench runs each part a number of times and reports the min, median and max of
+parsing and solving separately. It takes the same options as running a day,
+along with:
+    -n, --runs <n>          how many times to run each part, 10 by default
+    --save <file>           write the results to a JSON baseline
+    --baseline <file>       compare with an earlier baseline and exit nonzero
+                            when a median got slower
+    --threshold <percent>   how much slower a median may get, 10 by default
+
+crosscheck runs the python3 reference script py/day<N>.py of each day that has
+one on the same input and flags every answer that differs from ours.
+
+generate prints a random input for a day. --size is roughly the number of
+records, or the side of the grid for grid days, and is 10 by default. Without
+--seed a seed is picked and printed to stderr so the input can be made again.
+
+difftest compares the parts that have a second, simpler solver (day 5 part 2,
+day 7 part 2 and day 12 part 1) with it, on the given file or on --runs
+generated inputs, 100 by default. The nth input is generated from the seed plus
+n. The first input they disagree on is shrunk as far as it still disagrees.
+
+options:
+    -p, --part <1|2>    only run the given part
+    -t, --test          read inputs/day<N>/testdata.txt instead of data.txt
+    --format <text|json>
+                        how to print the answers. json prints an object per
+                        part with its day, part, answer, duration in seconds,
+                        input and error
+    --explain           day 1 only: print every line with its first and last
+                        digit highlighted, the value read from it and the
+                        running total
+    --csv               day 1 only: the same as --explain but as CSV
+    --vocabulary <file> day 1 only: read digits as the tokens in the file, a
+                        `<token> <value>` pair per line, and print the total
+    --bag <cubes>       day 2 only: check every game against a bag like
+                        `12 red, 13 green, 14 blue` instead of the puzzle's,
+                        printing whether each is possible, the fewest cubes
+                        it could be played with and its power
+    --bag-file <file>   day 2 only: the same as --bag with the bag read from
+                        a file
+    --stats             day 2 only: print the fewest, most and mean cubes drawn
+                        of each colour and how many games stay within the bag
+    --validate          day 2 only: report every malformed line, game id that
+                        isn't its line number and colour that isn't in the bag
+    --part-numbers      day 3 only: list every number next to a symbol
+    --gears <n>         day 3 only: list every `*` next to exactly n numbers
+    --adjacent <symbol> day 3 only: list every number next to the symbol
+    --gear-symbols <symbols>
+                        day 3 only: the symbols that are gears instead of `*`.
+                        Any of the gear options lists every gear with its
+                        ratio under the rules given
+    --gear-numbers <n|n..|n..=m>
+                        day 3 only: how many numbers a gear is next to rather
+                        than exactly 2. Symbols next to more are reported
+    --gear-ratio <product|sum|max>
+                        day 3 only: how the numbers next to a gear are
+                        combined, product by default
+    --cascade <text|csv|dot>
+                        day 4 only: print the matches, points and copies of
+                        every card along with the cards it won copies from,
+                        as text, CSV or a Graphviz graph
+    --scoring <linear|doubling|<points>>
+                        day 4 only: print the points of every card when a
+                        card scores a point per match, doubles like the puzzle
+                        or scores from a table like `0,1,3,6` listing the
+                        points for 0, 1, 2 and more matches. With --cascade it
+                        sets the points shown there
+    -v, --verbose       log more detail to stderr, repeat for even more. Without
+                        it the level is read from AOC_LOG, one of error, warn,
+                        info, debug or trace
+    -h, --help          print this message";
+
+/// Where a day should read its puzzle input from
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum InputChoice {
+    Data,
+    Test,
+    Path(String),
+}
+
+impl InputChoice {
+    pub(crate) fn input(&self, day: u32) -> Input {
+        match self {
+            InputChoice::Data => Input::data(day),
+            InputChoice::Test => Input::test(day),
+            InputChoice::Path(path) => Input::from_arg(path),
+        }
+    }
+}
+
+/// What a day 1 run with a calibration option prints
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Report {
+    Total,
+    Explain,
+    Csv,
+}
+
+/// What a day 2 run with a bag option prints
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum GameReport {
+    Games,
+    Stats,
+    Validate,
+}
+
+/// How a day 4 run with --cascade prints the cascade
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum CascadeFormat {
+    Text,
+    Csv,
+    Dot,
+}
+
+impl FromStr for CascadeFormat {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, ()> {
+        match s {
+            "text" => Ok(CascadeFormat::Text),
+            "csv" => Ok(CascadeFormat::Csv),
+            "dot" => Ok(CascadeFormat::Dot),
+            _ => Err(()),
+        }
+    }
+}
+
+/// How the answers of a run are printed
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub(crate) enum Format {
+    #[default]
+    Text,
+    Json,
+}
+
+impl FromStr for Format {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, ()> {
+        match s {
+            "text" => Ok(Format::Text),
+            "json" => Ok(Format::Json),
+            _ => Err(()),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct BenchOptions {
+    pub(crate) runs: usize,
+    pub(crate) save: Option<String>,
+    pub(crate) baseline: Option<String>,
+    pub(crate) threshold: f64,
+}
+
+impl Default for BenchOptions {
+    fn default() -> Self {
+        Self {
+            runs: 10,
+            save: None,
+            baseline: None,
+            threshold: 10.0,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub(crate) enum Command {
+    Help,
+    List,
+    All(Format),
+    Verify(RangeInclusive<u32>),
+    Generate {
+        day: u32,
+        size: usize,
+        seed: Option<u64>,
+    },
+    Difftest {
+        days: RangeInclusive<u32>,
+        path: Option<String>,
+        runs: usize,
+        size: usize,
+        seed: Option<u64>,
+    },
+    Crosscheck {
+        days: RangeInclusive<u32>,
+        parts: Vec<usize>,
+        input: InputChoice,
+    },
+    Bench {
+        days: RangeInclusive<u32>,
+        parts: Vec<usize>,
+        input: InputChoice,
+        options: BenchOptions,
+    },
+    Calibrate {
+        part: usize,
+        input: InputChoice,
+        vocabulary: Option<String>,
+        report: Report,
+    },
+    Games {
+        input: InputChoice,
+        bag: Option<Input>,
+        report: GameReport,
+    },
+    Schematic {
+        input: InputChoice,
+        query: Query,
+    },
+    Gears {
+        input: InputChoice,
+        rules: GearRules,
+    },
+    Cascade {
+        input: InputChoice,
+        format: CascadeFormat,
+        scoring: Scoring,
+    },
+    Scores {
+        input: InputChoice,
+        scoring: Scoring,
+    },
+    Run {
+        days: RangeInclusive<u32>,
+        parts: Vec<usize>,
+        input: InputChoice,
+        format: Format,
+    },
+}
+
+fn parse_days(arg: &str) -> Option<RangeInclusive<u32>> {
+    if let Some((first, last)) = arg.split_once("..=") {
+        Some(first.parse().ok()?..=last.parse().ok()?)
+    } else if let Some((first, last)) = arg.split_once("..") {
+        Some(first.parse().ok()?..=last.parse::<u32>().ok()?.checked_sub(1)?)
+    } else {
+        let day = arg.parse().ok()?;
+        Some(day..=day)
+    }
+}
+
+fn parse_part(arg: Option<String>) -> Result<usize, String> {
+    match arg.as_deref() {
+        Some("1") => Ok(1),
+        Some("2") => Ok(2),
+        Some(other) => Err(format!("invalid part `{}`, expected 1 or 2", other)),
+        None => Err("--part requires a value".to_owned()),
+    }
+}
+
+fn parse_value<T: FromStr>(flag: &str, arg: Option<String>) -> Result<T, String> {
+    let Some(arg) = arg else {
+        return Err(format!("{} requires a value", flag));
+    };
+    arg.parse()
+        .map_err(|_| format!("invalid value `{}` for {}", arg, flag))
+}
+
+fn parse_run(days: RangeInclusive<u32>, mut args: impl Iterator<Item = String>) -> Result<Command, String> {
+    let mut format = Format::default();
+    let mut vocabulary = None;
+    let mut report = None;
+    let mut bag = None;
+    let mut game_report = None;
+    let mut query = None;
+    let mut rules = None;
+    let mut cascade = None;
+    let mut scoring = None;
+    let run_args = parse_run_args(&days, &mut args, |flag, args| {
+        match flag {
+            "--format" => format = parse_value(flag, args.next())?,
+            "--vocabulary" => vocabulary = Some(parse_value(flag, args.next())?),
+            "--explain" => report = Some(Report::Explain),
+            "--csv" => report = Some(Report::Csv),
+            "--bag" => {
+                let cubes: String = parse_value(flag, args.next())?;
+                bag = Some(Input::text("--bag", &cubes));
+            }
+            "--bag-file" => bag = Some(Input::from_arg(&parse_value::<String>(flag, args.next())?)),
+            "--stats" => game_report = Some(GameReport::Stats),
+            "--validate" => game_report = Some(GameReport::Validate),
+            "--part-numbers" => query = Some(Query::PartNumbers),
+            "--gears" => query = Some(Query::Gears(parse_value(flag, args.next())?)),
+            "--adjacent" => query = Some(Query::Adjacent(parse_value(flag, args.next())?)),
+            "--cascade" => cascade = Some(parse_value(flag, args.next())?),
+            "--scoring" => scoring = Some(parse_value(flag, args.next())?),
+            "--gear-symbols" => {
+                let symbols: String = parse_value(flag, args.next())?;
+                rules.get_or_insert_with(GearRules::default).symbols = symbols.chars().collect();
+            }
+            "--gear-numbers" => {
+                rules.get_or_insert_with(GearRules::default).neighbours = parse_value(flag, args.next())?
+            }
+            "--gear-ratio" => {
+                rules.get_or_insert_with(GearRules::default).aggregate = parse_value(flag, args.next())?
+            }
+            _ => return Ok(false),
+        }
+        Ok(true)
+    })?;
+    let Some((parts, input)) = run_args else {
+        return Ok(Command::Help);
+    };
+
+    if cascade.is_some() || scoring.is_some() {
+        if days != (4..=4) {
+            return Err("--cascade and --scoring only work for day 4".to_owned());
+        }
+        let scoring = scoring.unwrap_or(Scoring::Doubling);
+        return Ok(match cascade {
+            Some(format) => Command::Cascade {
+                input,
+                format,
+                scoring,
+            },
+            None => Command::Scores { input, scoring },
+        });
+    }
+
+    if query.is_some() || rules.is_some() {
+        if days != (3..=3) {
+            return Err("--part-numbers, --gears, --adjacent and the gear rules only work for day 3".to_owned());
+        }
+        return Ok(match (query, rules) {
+            (Some(query), None) => Command::Schematic { input, query },
+            (None, Some(rules)) => Command::Gears { input, rules },
+            _ => return Err("the gear rules cannot be combined with a query".to_owned()),
+        });
+    }
+
+    if bag.is_some() || game_report.is_some() {
+        if days != (2..=2) {
+            return Err("--bag, --bag-file, --stats and --validate only work for day 2".to_owned());
+        }
+        return Ok(Command::Games {
+            input,
+            bag,
+            report: game_report.unwrap_or(GameReport::Games),
+        });
+    }
+
+    if report.is_some() || vocabulary.is_some() {
+        if days != (1..=1) {
+            return Err("--explain, --csv and --vocabulary only work for day 1".to_owned());
+        }
+        return Ok(Command::Calibrate {
+            // written digits only count in part 2, so that's the one explained
+            // unless part 1 alone was asked for
+            part: if parts == [1] { 1 } else { 2 },
+            input,
+            vocabulary,
+            report: report.unwrap_or(Report::Total),
+        });
+    }
+
+    Ok(Command::Run {
+        days,
+        parts,
+        input,
+        format,
+    })
+}
+
+fn parse_all(mut args: impl Iterator<Item = String>) -> Result<Command, String> {
+    let mut format = Format::default();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--format" => format = parse_value(&arg, args.next())?,
+            "-h" | "--help" => return Ok(Command::Help),
+            _ => return Err(format!("unexpected argument `{}`", arg)),
+        }
+    }
+
+    Ok(Command::All(format))
+}
+
+fn parse_command_days(command: &str, arg: Option<String>) -> Result<RangeInclusive<u32>, String> {
+    let Some(arg) = arg else {
+        return Err(format!("{} requires the days to run", command));
+    };
+    match parse_days(&arg) {
+        Some(days) if !days.is_empty() => Ok(days),
+        _ => Err(format!("invalid days `{}`", arg)),
+    }
+}
+
+fn parse_verify(mut args: impl Iterator<Item = String>) -> Result<Command, String> {
+    let days = match args.next() {
+        None => 1..=DAYS.len() as u32,
+        Some(arg) if arg == "-h" || arg == "--help" => return Ok(Command::Help),
+        arg => parse_command_days("verify", arg)?,
+    };
+    match args.next() {
+        Some(arg) => Err(format!("unexpected argument `{}`", arg)),
+        None => Ok(Command::Verify(days)),
+    }
+}
+
+fn parse_generate(mut args: impl Iterator<Item = String>) -> Result<Command, String> {
+    let day = match args.next() {
+        Some(arg) => parse_value("generate", Some(arg))?,
+        None => return Err("generate requires a day".to_owned()),
+    };
+    let mut size = 10;
+    let mut seed = None;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--size" => size = parse_value(&arg, args.next())?,
+            "--seed" => seed = Some(parse_value(&arg, args.next())?),
+            "-h" | "--help" => return Ok(Command::Help),
+            _ => return Err(format!("unexpected argument `{}`", arg)),
+        }
+    }
+    if size == 0 {
+        return Err("--size must be at least 1".to_owned());
+    }
+
+    Ok(Command::Generate { day, size, seed })
+}
+
+fn parse_difftest(mut args: impl Iterator<Item = String>) -> Result<Command, String> {
+    let days = parse_command_days("difftest", args.next())?;
+    let mut path = None;
+    let mut runs = 100;
+    let mut size = 10;
+    let mut seed = None;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-n" | "--runs" => runs = parse_value(&arg, args.next())?,
+            "--size" => size = parse_value(&arg, args.next())?,
+            "--seed" => seed = Some(parse_value(&arg, args.next())?),
+            "-h" | "--help" => return Ok(Command::Help),
+            flag if flag.starts_with('-') && flag.len() > 1 => {
+                return Err(format!("unknown option `{}`", flag))
+            }
+            _ if path.is_some() => return Err(format!("unexpected argument `{}`", arg)),
+            _ => path = Some(arg),
+        }
+    }
+    if runs == 0 || size == 0 {
+        return Err("--runs and --size must be at least 1".to_owned());
+    }
+    if path.is_some() && days.start() != days.end() {
+        return Err("a file can only be given when running a single day".to_owned());
+    }
+
+    Ok(Command::Difftest {
+        days,
+        path,
+        runs,
+        size,
+        seed,
+    })
+}
+
+fn parse_crosscheck(mut args: impl Iterator<Item = String>) -> Result<Command, String> {
+    let days = parse_command_days("crosscheck", args.next())?;
+    let Some((parts, input)) = parse_run_args(&days, &mut args, |_, _| Ok(false))? else {
+        return Ok(Command::Help);
+    };
+    Ok(Command::Crosscheck { days, parts, input })
+}
+
+fn parse_bench(mut args: impl Iterator<Item = String>) -> Result<Command, String> {
+    let days = parse_command_days("bench", args.next())?;
+
+    let mut options = BenchOptions::default();
+    let run_args = parse_run_args(&days, &mut args, |flag, args| {
+        match flag {
+            "-n" | "--runs" => options.runs = parse_value(flag, args.next())?,
+            "--save" => options.save = Some(parse_value(flag, args.next())?),
+            "--baseline" => options.baseline = Some(parse_value(flag, args.next())?),
+            "--threshold" => options.threshold = parse_value(flag, args.next())?,
+            _ => return Ok(false),
+        }
+        Ok(true)
+    })?;
+    let Some((parts, input)) = run_args else {
+        return Ok(Command::Help);
+    };
+    if options.runs == 0 {
+        return Err("--runs must be at least 1".to_owned());
+    }
+
+    Ok(Command::Bench {
+        days,
+        parts,
+        input,
+        options,
+    })
+}
+
+/// Parse the options shared by every command that runs days. `extra` is given each
+/// flag first and returns whether it handled it. Returns `None` when help was asked
+/// for.
+fn parse_run_args<I: Iterator<Item = String>>(
+    days: &RangeInclusive<u32>,
+    args: &mut I,
+    mut extra: impl FnMut(&str, &mut I) -> Result<bool, String>,
+) -> Result<Option<(Vec<usize>, InputChoice)>, String> {
+    let mut parts = vec![];
+    let mut test = false;
+    let mut path = None;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-p" | "--part" => parts.push(parse_part(args.next())?),
+            "-t" | "--test" => test = true,
+            "-h" | "--help" => return Ok(None),
+            flag if extra(flag, args)? => {}
+            flag if flag.starts_with('-') && flag.len() > 1 => {
+                return Err(format!("unknown option `{}`", flag))
+            }
+            _ if path.is_some() => return Err(format!("unexpected argument `{}`", arg)),
+            _ => path = Some(arg),
+        }
+    }
+
+    let input = match (path, test) {
+        (Some(_), true) => return Err("--test cannot be combined with a file".to_owned()),
+        (Some(_), false) if days.start() != days.end() => {
+            return Err("a file can only be given when running a single day".to_owned())
+        }
+        (Some(path), false) => InputChoice::Path(path),
+        (None, true) => InputChoice::Test,
+        (None, false) => InputChoice::Data,
+    };
+
+    if parts.is_empty() {
+        parts = vec![1, 2];
+    }
+    parts.sort();
+    parts.dedup();
+
+    Ok(Some((parts, input)))
+}
+
+/// Remove every `-v` and `--verbose` flag from the arguments, wherever they are, and
+/// count how many were given. `-vv` counts as two.
+pub(crate) fn take_verbosity(args: impl Iterator<Item = String>) -> (usize, Vec<String>) {
+    let mut verbosity = 0;
+    let mut rest = vec![];
+
+    for arg in args {
+        if arg == "--verbose" {
+            verbosity += 1;
+        } else if arg.len() > 1 && arg.starts_with('-') && arg[1..].chars().all(|c| c == 'v') {
+            verbosity += arg.len() - 1;
+        } else {
+            rest.push(arg);
+        }
+    }
+
+    (verbosity, rest)
+}
+
+/// Parse the command line, not including the program name
+pub(crate) fn parse(mut args: impl Iterator<Item = String>) -> Result<Command, String> {
+    let Some(first) = args.next() else {
+        return Err("missing command".to_owned());
+    };
+
+    match first.as_str() {
+        "-h" | "--help" | "help" => Ok(Command::Help),
+        "list" => Ok(Command::List),
+        "all" => parse_all(args),
+        "bench" => parse_bench(args),
+        "crosscheck" => parse_crosscheck(args),
+        "difftest" => parse_difftest(args),
+        "generate" => parse_generate(args),
+        "verify" => parse_verify(args),
+        arg => match parse_days(arg) {
+            Some(days) if days.is_empty() => Err(format!("empty range of days `{}`", arg)),
+            Some(days) => parse_run(days, args),
+            None => Err(format!("unknown command `{}`", arg)),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_args(args: &str) -> Result<Command, String> {
+        parse(args.split_whitespace().map(str::to_owned))
+    }
+
+    #[test]
+    fn verify_days() {
+        assert!(matches!(parse_args("verify"), Ok(Command::Verify(days)) if days == (1..=DAYS.len() as u32)));
+        assert!(matches!(parse_args("verify 3"), Ok(Command::Verify(days)) if days == (3..=3)));
+        assert!(matches!(parse_args("verify 2..=4"), Ok(Command::Verify(days)) if days == (2..=4)));
+    }
+
+    #[test]
+    fn verify_rejects_bad_days_and_extra_arguments() {
+        assert_eq!(parse_args("verify 5..3").unwrap_err(), "invalid days `5..3`");
+        assert_eq!(parse_args("verify 3..3").unwrap_err(), "invalid days `3..3`");
+        assert_eq!(parse_args("verify x").unwrap_err(), "invalid days `x`");
+        assert_eq!(parse_args("verify 3 junk").unwrap_err(), "unexpected argument `junk`");
+    }
+}
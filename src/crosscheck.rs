@@ -0,0 +1,113 @@
+use crate::days::registry;
+use crate::input::Input;
+use crate::runner;
+use std::{env, fs, process};
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// The interpreter the reference scripts are run with
+pub const PYTHON: &str = "python3";
+
+/// The reference implementation of `day` at `py/day<N>.py`, if there is one
+pub fn script(day: u32) -> Option<PathBuf> {
+    let path = PathBuf::from(format!("py/day{}.py", day));
+    path.is_file().then_some(path)
+}
+
+/// The answers printed by a reference script, one per line in part order. Each
+/// line is labelled like `Problem 1 solution: 42` or `Problem1: 42`, so the answer
+/// is whatever follows the last `:`.
+pub fn parse_answers(stdout: &str) -> Vec<String> {
+    stdout
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(|line| match line.rsplit_once(':') {
+            Some((_, answer)) => answer.trim().to_owned(),
+            None => line.to_owned(),
+        })
+        .collect()
+}
+
+/// Run a reference script on `input` and return the answers it printed. The
+/// scripts take the path of their input as their only argument and may open it
+/// more than once, so inputs that aren't files are written to a temporary file.
+pub fn run_script(script: &Path, input: &Input) -> Result<Vec<String>, String> {
+    let path = match input.path() {
+        Some(path) => PathBuf::from(path),
+        None => {
+            let path = env::temp_dir().join(format!("aoc2023-crosscheck-{}.txt", process::id()));
+            let data = input.read_to_string().map_err(|e| e.to_string())?;
+            fs::write(&path, data).map_err(|e| format!("{}: {}", path.display(), e))?;
+            path
+        }
+    };
+
+    let output = Command::new(PYTHON).arg(script).arg(&path).output();
+    if input.path().is_none() {
+        let _ = fs::remove_file(&path);
+    }
+
+    let output = output.map_err(|e| format!("could not run {}: {}", PYTHON, e))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!(
+            "{} exited with {}: {}",
+            script.display(),
+            output.status,
+            stderr.trim().lines().last().unwrap_or("")
+        ));
+    }
+
+    Ok(parse_answers(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// Compare the answers of every registered day in `days` with its reference script,
+/// printing a line per check. Days without a script are skipped. Returns whether
+/// every answer agreed.
+pub fn crosscheck(days: RangeInclusive<u32>, parts: &[usize], input: impl Fn(u32) -> Input) -> bool {
+    let mut agreed = 0;
+    let mut differed = 0;
+
+    for day in days.filter_map(registry::get) {
+        let Some(script) = script(day.number) else {
+            println!("SKIP day {}: no reference script", day.number);
+            continue;
+        };
+
+        let input = input(day.number);
+        let reference = match run_script(&script, &input) {
+            Ok(answers) => answers,
+            Err(e) => {
+                println!("FAIL day {}: {}", day.number, e);
+                differed += 1;
+                continue;
+            }
+        };
+
+        for &part in parts {
+            let outcome = runner::run_part(day, part, &input);
+            let got = match outcome.result {
+                Ok(answer) => answer.to_string(),
+                Err(e) => format!("error: {}", e),
+            };
+            let want = reference
+                .get(part - 1)
+                .map_or("<no answer printed>", String::as_str);
+
+            if got == want {
+                println!("AGREE {} part {}: {}", input.name(), part, got);
+                agreed += 1;
+            } else {
+                println!("DIFFER {} part {}", input.name(), part);
+                println!("    rust:   {}", got);
+                println!("    python: {}", want);
+                differed += 1;
+            }
+        }
+    }
+
+    println!("{} agreed, {} differed", agreed, differed);
+    differed == 0
+}
@@ -0,0 +1,91 @@
+use serde::Serialize;
+use std::fmt::{self, Display};
+
+/// The answer to one part of a day. Most puzzles have a numeric answer but a
+/// few are answered with text.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize)]
+#[serde(untagged)]
+pub enum Answer {
+    Int(i64),
+    /// Only for unsigned answers that are too big for `Int`, so that every number
+    /// has exactly one way of being written
+    UInt(u64),
+    Str(String),
+}
+
+impl Display for Answer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Answer::Int(n) => write!(f, "{}", n),
+            Answer::UInt(n) => write!(f, "{}", n),
+            Answer::Str(s) => write!(f, "{}", s),
+        }
+    }
+}
+
+macro_rules! int_answer {
+    ($($t:ty),+) => {
+        $(
+            impl From<$t> for Answer {
+                fn from(n: $t) -> Self {
+                    Answer::Int(n.into())
+                }
+            }
+        )+
+    };
+}
+
+macro_rules! uint_answer {
+    ($($t:ty),+) => {
+        $(
+            impl From<$t> for Answer {
+                fn from(n: $t) -> Self {
+                    // usize is at most 64 bits on every supported target
+                    let n = n as u64;
+                    i64::try_from(n).map_or(Answer::UInt(n), Answer::Int)
+                }
+            }
+        )+
+    };
+}
+
+int_answer!(i32, i64);
+uint_answer!(u32, u64, usize);
+
+impl From<String> for Answer {
+    fn from(s: String) -> Self {
+        Answer::Str(s)
+    }
+}
+
+impl From<&str> for Answer {
+    fn from(s: &str) -> Self {
+        Answer::Str(s.to_owned())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unsigned_answers_that_fit_are_ints() {
+        assert_eq!(Answer::from(5_u64), Answer::Int(5));
+        assert_eq!(Answer::from(i64::MAX as usize), Answer::Int(i64::MAX));
+        assert_eq!(Answer::from(u32::MAX), Answer::Int(u32::MAX.into()));
+    }
+
+    #[test]
+    fn unsigned_answers_past_i64_do_not_wrap() {
+        let answer = Answer::from(u64::MAX);
+        assert_eq!(answer, Answer::UInt(u64::MAX));
+        assert_eq!(answer.to_string(), "18446744073709551615");
+        assert_eq!(serde_json::to_string(&answer).unwrap(), "18446744073709551615");
+        assert_eq!(Answer::from(1_u64 << 63).to_string(), "9223372036854775808");
+    }
+
+    #[test]
+    fn negative_answers() {
+        assert_eq!(Answer::from(-3_i32).to_string(), "-3");
+    }
+}
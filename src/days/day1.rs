@@ -1,80 +1,389 @@
+use crate::days::answer::Answer;
 use crate::days::solution::Solution;
-use regex::Regex;
-use std::io::Result;
-
-pub(crate) struct Day1;
-
-impl Day1 {
-    fn word_to_digit(word: String) -> String {
-        // map a written number or an ascii digit to the
-        // corresponding ascii digit. Panic if anything else
-        // is passed
-        let digit = match word.as_str() {
-            "one" => "1",
-            "two" => "2",
-            "three" => "3",
-            "four" => "4",
-            "five" => "5",
-            "six" => "6",
-            "seven" => "7",
-            "eight" => "8",
-            "nine" => "9",
-            a if a.chars().nth(0).unwrap().is_ascii_digit() => a,
-            _ => panic!(),
-        };
-        digit.to_owned()
+use crate::error::{Error, InputLine, Result};
+use crate::generate::Rng;
+use crate::input::Input;
+use colored::Colorize;
+
+/// The written digits, in order from one
+const WORDS: [&str; 9] = ["one", "two", "three", "four", "five", "six", "seven", "eight", "nine"];
+
+/// A digit token found on a line, `start..end` being its byte range in the line
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Token {
+    pub start: usize,
+    pub end: usize,
+    pub value: u32,
+}
+
+/// The first and last digit tokens of a line
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Calibration {
+    pub first: Token,
+    pub last: Token,
+    value: u64,
+}
+
+impl Calibration {
+    /// The digits of the first token followed by the digits of the last, so `ten`
+    /// then `one` is 101 and `one` then `ten` is 110. Fails when that is too big.
+    fn new(first: Token, last: Token) -> Option<Self> {
+        let shift = 10_u64.checked_pow(last.value.checked_ilog10().unwrap_or(0) + 1)?;
+        let value = u64::from(first.value)
+            .checked_mul(shift)?
+            .checked_add(u64::from(last.value))?;
+        Some(Calibration { first, last, value })
+    }
+
+    pub fn value(&self) -> u64 {
+        self.value
     }
 }
 
-impl Solution for Day1 {
-    fn problem1(path: &str) -> Result<()> {
-        let lines = Day1::read_input_into_lines(path)?;
+/// The tokens that are read as digits, along with the digit each stands for
+#[derive(Debug, Clone, Default)]
+pub struct Vocabulary {
+    tokens: Vec<(String, u32)>,
+}
 
-        let total = lines
+impl Vocabulary {
+    /// Only the ascii digits
+    pub fn digits() -> Self {
+        (0..10).fold(Vocabulary::default(), |vocabulary, d| vocabulary.with(&d.to_string(), d))
+    }
+
+    /// The ascii digits and the english words for one to nine
+    pub fn english() -> Self {
+        WORDS
             .iter()
-            .map(|s| {
-                let digits: Vec<char> = s.chars().filter(|c| c.is_ascii_digit()).collect();
-                String::from_iter([digits[0], digits[digits.len() - 1]])
-                    .parse::<u32>()
-                    .unwrap()
-            })
-            .sum::<u32>();
-
-        println!("Got answer to problem1 part 1: {}", total);
-        Ok(())
-    }
-
-    fn problem2(path: &str) -> Result<()> {
-        let lines = Day1::read_input_into_lines(path)?;
-        // Rust regex doesnt allow for overlapping matches. So in the case of `oneighthree` it
-        // will only find `one` if capture_iters is called. Since we only need the first and
-        // the last match though we can just reverse the line and the use a reversed regex to
-        // find the digit or written digit. You could also use the same regex and the walk
-        // backward in the string applying it to each substring but this is more work
-        let digits_regex = Regex::new(r"\d|one|two|three|four|five|six|seven|eight|nine").unwrap();
-        let rev_digits_regex =
-            Regex::new(r"\d|eno|owt|eerht|ruof|evif|xis|neves|thgie|enin").unwrap();
-        let total = lines
+            .zip(1..)
+            .fold(Vocabulary::digits(), |vocabulary, (word, d)| vocabulary.with(word, d))
+    }
+
+    pub fn with(mut self, token: &str, value: u32) -> Self {
+        self.tokens.push((token.to_owned(), value));
+        self
+    }
+
+    /// A vocabulary with a `<token> <value>` pair on every line
+    pub fn parse(input: &Input) -> Result<Self> {
+        let path = input.name();
+        let mut vocabulary = Vocabulary::default();
+        for (i, text) in input.lines()?.enumerate() {
+            let text = text?;
+            let line = InputLine::new(path, i, &text);
+            let (token, value) = match line.text.split_whitespace().collect::<Vec<_>>()[..] {
+                [token, value] => (token, line.number(value)?),
+                [] => continue,
+                _ => return Err(line.error("expected `<token> <value>`")),
+            };
+            vocabulary = vocabulary.with(token, value);
+        }
+        Ok(vocabulary)
+    }
+
+    /// Every token on `line` from left to right, including the ones that overlap
+    /// like the `one` and `eight` in `oneight`. Tokens that start at the same place
+    /// come longest first.
+    pub fn tokens<'a>(&'a self, line: &'a str) -> impl Iterator<Item = Token> + 'a {
+        line.char_indices().flat_map(move |(start, _)| {
+            let mut found: Vec<Token> = self
+                .tokens
+                .iter()
+                .filter(|(token, _)| line[start..].starts_with(token.as_str()))
+                .map(|(token, value)| Token {
+                    start,
+                    end: start + token.len(),
+                    value: *value,
+                })
+                .collect();
+            found.sort_by_key(|t| std::cmp::Reverse(t.end));
+            found
+        })
+    }
+
+    /// The first and last tokens of `line`, which may be the same one
+    pub fn calibration(&self, line: &InputLine) -> Result<Calibration> {
+        let mut tokens = self.tokens(line.text);
+        let Some(first) = tokens.next() else {
+            return Err(line.error("no digits on line"));
+        };
+        let last = tokens.fold(first, |last, t| if t.start > last.start { t } else { last });
+        Calibration::new(first, last).ok_or_else(|| line.error("calibration value is too large"))
+    }
+
+    /// The calibration of every line along with the running total
+    pub fn explain(&self, path: &str, lines: &[String]) -> Vec<Explained> {
+        let mut total = 0_u64;
+        lines
             .iter()
             .enumerate()
-            .map(move |(_, s)| {
-                let first = Day1::word_to_digit(digits_regex.find(s).unwrap().as_str().to_owned());
-                let last = Day1::word_to_digit(
-                    rev_digits_regex
-                        .find(&s.to_owned().chars().rev().collect::<String>())
-                        .unwrap()
-                        .as_str()
-                        .chars()
-                        .rev()
-                        .collect::<String>(),
-                );
-                let num = String::from_iter([first, last]);
-                num.parse::<u32>().unwrap()
+            .map(|(i, s)| {
+                let line = InputLine::new(path, i, s);
+                let calibration = self.calibration(&line).map_err(|e| e.message());
+                total = calibration.as_ref().map_or(total, |c| total.saturating_add(c.value()));
+                Explained {
+                    number: line.number,
+                    text: line.text.to_owned(),
+                    calibration,
+                    total,
+                }
             })
-            .sum::<u32>();
+            .collect()
+    }
+
+    /// The sum of the calibration values of every line
+    pub fn total(&self, path: &str, lines: &[String]) -> Result<u64> {
+        let mut total = 0_u64;
+        for (i, s) in lines.iter().enumerate() {
+            let value = self.calibration(&InputLine::new(path, i, s))?.value();
+            total = total
+                .checked_add(value)
+                .ok_or_else(|| Error::no_solution(path, "the total is too large"))?;
+        }
+        Ok(total)
+    }
+}
+
+/// A line along with the calibration read from it and the total of every line up to
+/// and including it. Lines whose calibration can't be read keep why and add nothing.
+#[derive(Debug, Clone)]
+pub struct Explained {
+    pub number: usize,
+    pub text: String,
+    pub calibration: std::result::Result<Calibration, String>,
+    pub total: u64,
+}
+
+impl Explained {
+    pub fn token(&self, token: Token) -> &str {
+        &self.text[token.start..token.end]
+    }
+
+    /// The line with its first token in green and its last in cyan. Where the two
+    /// overlap, like in `oneight`, the shared part is yellow.
+    pub fn highlighted(&self) -> String {
+        let Ok(Calibration { first, last, .. }) = self.calibration else {
+            return self.text.clone();
+        };
+
+        let mut out = String::new();
+        for (i, c) in self.text.char_indices() {
+            let c = c.to_string();
+            let in_first = (first.start..first.end).contains(&i);
+            let in_last = (last.start..last.end).contains(&i);
+            out += &match (in_first, in_last) {
+                (true, true) if first != last => c.yellow().bold().to_string(),
+                (true, _) => c.green().bold().to_string(),
+                (_, true) => c.cyan().bold().to_string(),
+                _ => c,
+            };
+        }
+        out
+    }
+}
+
+/// Quote a CSV field when it needs it
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_owned()
+    }
+}
+
+pub fn print_explained(explained: &[Explained]) {
+    let width = explained.last().map_or(1, |e| e.number.to_string().len());
+    for e in explained {
+        match &e.calibration {
+            Ok(calibration) => println!(
+                "{:>width$}  {:>2}  {:>6}  {}",
+                e.number,
+                calibration.value(),
+                e.total,
+                e.highlighted(),
+            ),
+            Err(error) => println!("{:>width$}  {:>2}  {:>6}  {}  {}", e.number, "-", e.total, e.text, error.red()),
+        }
+    }
+}
+
+/// The explanation as CSV with a header, the columns being 1-based. Lines whose
+/// calibration can't be read leave the token columns empty and say why in `error`.
+pub fn explained_csv(explained: &[Explained]) -> String {
+    let mut out = "line,text,first,first_column,last,last_column,value,total,error\n".to_owned();
+    for e in explained {
+        let (tokens, error) = match &e.calibration {
+            Ok(c) => (
+                [
+                    csv_field(e.token(c.first)),
+                    (e.text[..c.first.start].chars().count() + 1).to_string(),
+                    csv_field(e.token(c.last)),
+                    (e.text[..c.last.start].chars().count() + 1).to_string(),
+                    c.value().to_string(),
+                ]
+                .join(","),
+                String::new(),
+            ),
+            Err(error) => (",,,,".to_owned(), csv_field(error)),
+        };
+        out += &format!("{},{},{},{},{}\n", e.number, csv_field(&e.text), tokens, e.total, error);
+    }
+    out
+}
+
+pub struct Day1;
+
+impl Solution for Day1 {
+    const DAY: u32 = 1;
+    const TITLE: &'static str = "Trebuchet?!";
+
+    type Parsed = Vec<String>;
+
+    fn parse(input: &Input) -> Result<Vec<String>> {
+        Day1::read_input_into_lines(input)
+    }
+
+    fn part1(input: &Input, lines: &Vec<String>) -> Result<Answer> {
+        Ok(Vocabulary::digits().total(input.name(), lines)?.into())
+    }
+
+    fn part2(input: &Input, lines: &Vec<String>) -> Result<Answer> {
+        Ok(Vocabulary::english().total(input.name(), lines)?.into())
+    }
+
+    fn generate(rng: &mut Rng, size: usize) -> String {
+        let mut out = String::new();
+        for _ in 0..size {
+            // every line needs a digit for part 1, the rest is letters and written digits
+            let mut tokens = vec![rng.range(1..=9).to_string()];
+            for _ in 0..rng.usize(0..=5) {
+                let token = match rng.below(3) {
+                    0 => rng.range(1..=9).to_string(),
+                    1 => rng.pick(&WORDS).to_string(),
+                    _ => rng.string("abcdefghijklmnopqrstuvwxyz", 1..=4),
+                };
+                tokens.push(token);
+            }
+            rng.shuffle(&mut tokens);
+            out += &tokens.concat();
+            out.push('\n');
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE_1: &str = "\
+1abc2
+pqr3stu8vwx
+a1b2c3d4e5f
+treb7uchet
+";
+
+    const EXAMPLE_2: &str = "\
+two1nine
+eightwothree
+abcone2threexyz
+xtwone3four
+4nineeightseven2
+zoneight234
+7pqrstsixteen
+";
+
+    #[test]
+    fn part1_example() {
+        let input = Input::text("example", EXAMPLE_1);
+        assert_eq!(Day1::problem1(&input).unwrap(), Answer::Int(142));
+    }
+
+    #[test]
+    fn part2_example() {
+        let input = Input::text("example", EXAMPLE_2);
+        assert_eq!(Day1::problem2(&input).unwrap(), Answer::Int(281));
+    }
+
+    fn total(vocabulary: &Vocabulary, text: &str) -> Result<u64> {
+        let lines: Vec<String> = text.lines().map(str::to_owned).collect();
+        vocabulary.total("test", &lines)
+    }
+
+    #[test]
+    fn overlapping_words_both_count() {
+        let english = Vocabulary::english();
+        assert_eq!(total(&english, "oneight").unwrap(), 18);
+        assert_eq!(total(&english, "eightwo").unwrap(), 82);
+        assert_eq!(total(&english, "xoneightx\neightwo\n").unwrap(), 18 + 82);
+    }
+
+    #[test]
+    fn values_with_more_than_one_digit_are_concatenated() {
+        let vocabulary = Input::text("vocabulary", "one 1\nten 10\n");
+        let vocabulary = Vocabulary::parse(&vocabulary).unwrap();
+        assert_eq!(total(&vocabulary, "ten3one").unwrap(), 101);
+        assert_eq!(total(&vocabulary, "one3ten").unwrap(), 110);
+        assert_eq!(total(&vocabulary, "tenxten").unwrap(), 1010);
+        assert_eq!(total(&vocabulary, "ten").unwrap(), 1010);
+    }
+
+    #[test]
+    fn too_large_values_are_an_error() {
+        let vocabulary = Vocabulary::default().with("big", u32::MAX);
+        assert!(total(&vocabulary, "big").is_err());
+    }
+
+    #[test]
+    fn line_without_digits_is_an_error() {
+        let err = total(&Vocabulary::digits(), "1abc2\nnothing\n").unwrap_err();
+        assert!(matches!(err, Error::Parse { line: 2, .. }));
+        assert!(err.to_string().contains("no digits on line"));
+    }
+
+    fn explain(vocabulary: &Vocabulary, text: &str) -> Vec<Explained> {
+        let lines: Vec<String> = text.lines().map(str::to_owned).collect();
+        vocabulary.explain("test", &lines)
+    }
+
+    #[test]
+    fn explained_as_csv() {
+        let explained = explain(&Vocabulary::english(), "xoneightx\na,1b\né2\nzero\n");
+        let csv = explained_csv(&explained);
+        let rows: Vec<&str> = csv.lines().collect();
+        assert_eq!(
+            rows,
+            vec![
+                "line,text,first,first_column,last,last_column,value,total,error",
+                "1,xoneightx,one,2,eight,4,18,18,",
+                "2,\"a,1b\",1,3,1,3,11,29,",
+                "3,é2,2,2,2,2,22,51,",
+                "4,zero,,,,,,51,no digits on line",
+            ]
+        );
+    }
+
+    #[test]
+    fn explanation_keeps_why_a_line_failed() {
+        let vocabulary = Vocabulary::default().with("big", u32::MAX);
+        let explained = explain(&vocabulary, "big\nnothing\n");
+        assert_eq!(explained[0].calibration, Err("calibration value is too large".to_owned()));
+        assert_eq!(explained[1].calibration, Err("no digits on line".to_owned()));
+        assert_eq!(explained[1].total, 0);
+    }
 
-        println!("Got answer for problem1 part2: {}", total);
+    #[test]
+    fn overlapping_tokens_are_highlighted() {
+        colored::control::set_override(true);
+        let explained = explain(&Vocabulary::english(), "oneight\n1x\nzero\n");
 
-        Ok(())
+        let green = |s: &str| s.green().bold().to_string();
+        let cyan = |s: &str| s.cyan().bold().to_string();
+        let expected = [green("o"), green("n"), "e".yellow().bold().to_string(), cyan("i"), cyan("g"), cyan("h"), cyan("t")];
+        assert_eq!(explained[0].highlighted(), expected.concat());
+        // a single token is both the first and the last
+        assert_eq!(explained[1].highlighted(), green("1") + "x");
+        assert_eq!(explained[2].highlighted(), "zero");
     }
 }
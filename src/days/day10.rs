@@ -1,185 +1,96 @@
+use super::answer::Answer;
 use super::solution::Solution;
 use colored::Colorize;
+use crate::direction::Direction;
+use crate::error::{Error, Result};
+use crate::generate::Rng;
+use crate::input::Input;
+use crate::debug;
+use crate::grid::{Grid, Point};
 use std::collections::{HashSet, VecDeque};
-use std::fs::File;
-use std::io::{prelude::*, BufReader, Result};
 
 pub struct Day10;
 
-#[derive(Clone, Copy, Debug, PartialEq, Hash, Eq)]
-struct Location {
-    x: usize,
-    y: usize,
-}
-
-#[derive(Clone, Copy, Debug, PartialEq)]
-enum Direction {
-    North,
-    South,
-    East,
-    West,
-}
-
-fn get_perpindicular(direction: Direction) -> [Direction; 2] {
-    match direction {
-        Direction::North | Direction::South => [Direction::West, Direction::East],
-        Direction::East | Direction::West => [Direction::North, Direction::South],
-    }
-}
-
-fn get_opposite_direction(direction: Direction) -> Direction {
-    match direction {
-        Direction::North => Direction::South,
-        Direction::South => Direction::North,
-        Direction::East => Direction::West,
-        Direction::West => Direction::East,
-    }
-}
-
 fn get_new_perpindicular(
     old_direction: Direction,
     new_direction: Direction,
     perpindicular: Direction,
 ) -> Direction {
     if new_direction == perpindicular {
-        get_opposite_direction(old_direction)
+        old_direction.opposite()
     } else {
         old_direction
     }
 }
 
-fn clamped_sub(i: usize, j: usize, clamp: usize) -> usize {
-    if (clamp + j) > i {
-        clamp
-    } else {
-        i - j
-    }
-}
-
-fn clamped_add(i: usize, j: usize, clamp: usize) -> usize {
-    if (clamp - j) < i {
-        clamp
-    } else {
-        i + j
-    }
+/// The pipe tiles and the position of the start tile `S`
+pub struct PipeGrid {
+    pub grid: Grid<char>,
+    pub start: Point,
 }
 
-impl Location {
-    fn new((x, y): (usize, usize)) -> Self {
-        Self { x, y }
-    }
-
-    fn north(&self) -> (Self, Direction) {
-        (
-            Self {
-                x: self.x,
-                y: clamped_sub(self.y, 1, 0),
-            },
-            Direction::North,
-        )
-    }
-
-    fn south(&self, max: usize) -> (Self, Direction) {
-        (
-            Self {
-                x: self.x,
-                y: clamped_add(self.y, 1, max),
-            },
-            Direction::South,
-        )
-    }
-
-    fn east(&self, max: usize) -> (Self, Direction) {
-        (
-            Self {
-                x: clamped_add(self.x, 1, max),
-                y: self.y,
-            },
-            Direction::East,
-        )
-    }
-
-    fn west(&self) -> (Self, Direction) {
-        (
-            Self {
-                x: clamped_sub(self.x, 1, 0),
-                y: self.y,
-            },
-            Direction::West,
-        )
-    }
-}
-
-struct PipeGrid {
-    grid: Vec<Vec<char>>,
-    start: Location,
-}
-
-fn get_loop(grid: &PipeGrid, start: Location) -> Vec<(Location, Direction)> {
-    let mut path: Vec<(Location, Direction)> = Vec::new();
+fn get_loop(path: &str, grid: &PipeGrid, start: Point) -> Result<Vec<(Point, Direction)>> {
+    let mut loop_path: Vec<(Point, Direction)> = Vec::new();
     let possible_directions = grid.find_possible_directions(&start);
-    let (mut current, mut direction) = possible_directions[0];
-    path.push((current, direction));
+    let Some(&(mut current, mut direction)) = possible_directions.first() else {
+        return Err(Error::no_solution(path, "no pipe connects to the start tile"));
+    };
+    loop_path.push((current, direction));
 
     while current != start {
-        let next = grid.get_next_direction(&current, direction);
+        let next = grid.get_next_direction(&current, direction).ok_or_else(|| {
+            Error::no_solution(
+                path,
+                format!("the loop is broken at x={} y={}", current.x, current.y),
+            )
+        })?;
         (current, direction) = next;
-        path.push(next);
+        loop_path.push(next);
     }
 
-    path
+    Ok(loop_path)
 }
 
-fn print_loop(grid: &PipeGrid, loop_path: &[(Location, Direction)], in_set: &HashSet<Location>) {
-    let loop_set: HashSet<&Location> = HashSet::from_iter(loop_path.iter().map(|p| &p.0));
-    for (y, row) in grid.grid.iter().enumerate() {
-        println!();
+/// The grid with the loop in bold and the tiles it encloses in red
+fn format_loop(grid: &PipeGrid, loop_path: &[(Point, Direction)], in_set: &HashSet<Point>) -> String {
+    let loop_set: HashSet<&Point> = HashSet::from_iter(loop_path.iter().map(|p| &p.0));
+    let mut out = String::new();
+    for (y, row) in grid.grid.rows().enumerate() {
+        out.push('\n');
         for (x, c) in row.iter().enumerate() {
-            let loc = Location::new((x, y));
+            let loc = Point::new(x, y);
             let mut to_bold = String::new();
             to_bold.push(*c);
             if loop_set.contains(&loc) {
-                print!("{}", to_bold.bold());
+                out += &to_bold.bold().to_string();
             } else if in_set.contains(&loc) {
-                print!("{}", to_bold.bold().red())
+                out += &to_bold.bold().red().to_string();
             } else {
-                print!("{}", to_bold);
+                out += &to_bold;
             }
         }
     }
-    println!()
+    out
 }
 
-fn get_loop_area(grid: PipeGrid, loop_path: Vec<(Location, Direction)>, begin: Location) -> usize {
+fn get_loop_area(grid: &PipeGrid, loop_path: Vec<(Point, Direction)>, begin: Point) -> usize {
     let mut path = VecDeque::from_iter(&loop_path);
     let loop_set = HashSet::from_iter(loop_path.iter().map(|p| p.0));
     let start = grid.find_possible_directions(&begin)[0];
     path.push_front(&start);
-    let perpindiculars = get_perpindicular(start.1);
+    let perpindiculars = [start.1.turn_left(), start.1.turn_right()];
     let mut sets = vec![];
     for perp in perpindiculars {
         let mut perpindicular = perp;
         let mut in_set = HashSet::new();
         for (location, direction) in loop_path.iter() {
-            let next_direction = grid.get_next_direction(location, *direction).1;
-            add_to_inner(
-                &mut in_set,
-                &loop_set,
-                *location,
-                perpindicular,
-                grid.max_x(),
-                grid.max_y(),
-            );
+            let next_direction = grid
+                .get_next_direction(location, *direction)
+                .map_or(*direction, |(_, d)| d);
+            add_to_inner(grid, &mut in_set, &loop_set, *location, perpindicular);
             if *direction != next_direction {
                 perpindicular = get_new_perpindicular(*direction, next_direction, perpindicular);
-                add_to_inner(
-                    &mut in_set,
-                    &loop_set,
-                    *location,
-                    perpindicular,
-                    grid.max_x(),
-                    grid.max_y(),
-                );
+                add_to_inner(grid, &mut in_set, &loop_set, *location, perpindicular);
             }
         }
         sets.push(in_set);
@@ -191,146 +102,94 @@ fn get_loop_area(grid: PipeGrid, loop_path: Vec<(Location, Direction)>, begin: L
         sets[0].clone()
     };
 
-    print_loop(&grid, &loop_path, &in_set);
+    debug!("loop and enclosed tiles:{}", format_loop(grid, &loop_path, &in_set));
     in_set.len()
 }
 
 fn add_to_inner(
-    inner: &mut HashSet<Location>,
-    loop_set: &HashSet<Location>,
-    loc: Location,
+    grid: &PipeGrid,
+    inner: &mut HashSet<Point>,
+    loop_set: &HashSet<Point>,
+    loc: Point,
     dir: Direction,
-    max_x: usize,
-    max_y: usize,
 ) {
-    match dir {
-        Direction::North => {
-            let mut prev_loc = loc;
-            let mut new_loc = prev_loc.north().0;
-            while !loop_set.contains(&new_loc) && prev_loc != new_loc {
-                inner.insert(new_loc);
-                prev_loc = new_loc;
-                new_loc = prev_loc.north().0;
-            }
-        }
-        Direction::South => {
-            let mut prev_loc = loc;
-            let mut new_loc = prev_loc.south(max_y).0;
-            while !loop_set.contains(&new_loc) && prev_loc != new_loc {
-                inner.insert(new_loc);
-                prev_loc = new_loc;
-                new_loc = prev_loc.south(max_y).0
-            }
-        }
-        Direction::West => {
-            let mut prev_loc = loc;
-            let mut new_loc = prev_loc.west().0;
-            while !loop_set.contains(&new_loc) && prev_loc != new_loc {
-                inner.insert(new_loc);
-                prev_loc = new_loc;
-                new_loc = prev_loc.west().0
-            }
-        }
-        Direction::East => {
-            let mut prev_loc = loc;
-            let mut new_loc = loc.east(max_x).0;
-            while !loop_set.contains(&new_loc) && prev_loc != new_loc {
-                inner.insert(new_loc);
-                prev_loc = new_loc;
-                new_loc = prev_loc.east(max_x).0
-            }
+    let mut current = loc;
+    while let Some((next, _)) = grid.step(&current, dir) {
+        if loop_set.contains(&next) {
+            break;
         }
+        inner.insert(next);
+        current = next;
     }
 }
 
-impl PipeGrid {
-    fn parse(path: &str) -> Result<Self> {
-        let file = File::open(path)?;
-        let reader = BufReader::new(file);
-        let mut grid: Vec<Vec<char>> = Vec::new();
-        let mut start: Option<(usize, usize)> = None;
-
-        for (i, line) in reader.lines().enumerate() {
-            if let Some(Some(j)) = line
-                .as_ref()
-                .ok()
-                .map(|pipes| pipes.chars().position(|c| c == 'S'))
-            {
-                start.replace((j, i));
-            }
-            grid.push(line?.chars().collect());
-        }
-
-        Ok(Self {
-            grid,
-            start: Location::new(start.unwrap()),
-        })
-    }
+/// Whether `p` and a diagonal neighbour are both set without either cell between them
+fn touches_corner(blob: &Grid<bool>, p: Point) -> bool {
+    [(-1, -1), (1, -1), (-1, 1), (1, 1)].into_iter().any(|(dx, dy)| {
+        let set = |dx, dy| blob.offset(p, dx, dy).is_some_and(|n| blob[n]);
+        set(dx, dy) && !set(dx, 0) && !set(0, dy)
+    })
+}
 
-    fn get_pipe(&self, loc: &Location) -> char {
-        self.grid[loc.y][loc.x]
+/// Whether any unset cell is cut off from the edge of the grid by set cells
+fn has_hole(blob: &Grid<bool>) -> bool {
+    let mut outside = HashSet::new();
+    let mut todo: Vec<Point> = blob
+        .points()
+        .filter(|p| !blob[*p] && (p.x == 0 || p.y == 0 || p.x == blob.width() - 1 || p.y == blob.height() - 1))
+        .collect();
+    while let Some(p) = todo.pop() {
+        if outside.insert(p) {
+            todo.extend(blob.neighbors4(p).filter(|n| !blob[*n]));
+        }
     }
+    blob.iter().any(|(p, set)| !set && !outside.contains(&p))
+}
 
-    fn max_y(&self) -> usize {
-        self.grid.len() - 1
+impl PipeGrid {
+    fn parse(input: &Input) -> Result<Self> {
+        let path = input.name();
+        let grid = Day10::read_input_into_grid(input)?;
+        Day10::validate_grid(input, &grid, "|-LJ7F.S")?;
+
+        let start = grid
+            .iter()
+            .find(|(_, c)| **c == 'S')
+            .map(|(p, _)| p)
+            .ok_or_else(|| Error::no_solution(path, "no start tile `S`"))?;
+        Ok(Self { grid, start })
     }
 
-    fn max_x(&self) -> usize {
-        self.grid[0].len() - 1
+    fn step(&self, current: &Point, direction: Direction) -> Option<(Point, Direction)> {
+        Some((self.grid.step(*current, direction)?, direction))
     }
 
-    fn get_next_direction(
-        &self,
-        current: &Location,
-        direction: Direction,
-    ) -> (Location, Direction) {
-        match (self.get_pipe(current), direction) {
-            ('|', Direction::North) => current.north(),
-            ('|', Direction::South) => current.south(self.max_y()),
-            ('-', Direction::East) => current.east(self.max_x()),
-            ('-', Direction::West) => current.west(),
-            ('F', Direction::North) => current.east(self.max_x()),
-            ('F', Direction::West) => current.south(self.max_y()),
-            ('7', Direction::North) => current.west(),
-            ('7', Direction::East) => current.south(self.max_y()),
-            ('L', Direction::South) => current.east(self.max_x()),
-            ('L', Direction::West) => current.north(),
-            ('J', Direction::South) => current.west(),
-            ('J', Direction::East) => current.north(),
-            ('S', _) => (*current, direction),
-            _ => panic!(),
-        }
+    fn get_next_direction(&self, current: &Point, direction: Direction) -> Option<(Point, Direction)> {
+        let next = match (self.grid[*current], direction) {
+            ('|', Direction::North) => Direction::North,
+            ('|', Direction::South) => Direction::South,
+            ('-', Direction::East) => Direction::East,
+            ('-', Direction::West) => Direction::West,
+            ('F', Direction::North) => Direction::East,
+            ('F', Direction::West) => Direction::South,
+            ('7', Direction::North) => Direction::West,
+            ('7', Direction::East) => Direction::South,
+            ('L', Direction::South) => Direction::East,
+            ('L', Direction::West) => Direction::North,
+            ('J', Direction::South) => Direction::West,
+            ('J', Direction::East) => Direction::North,
+            ('S', _) => return Some((*current, direction)),
+            _ => return None,
+        };
+        self.step(current, next)
     }
 
-    fn find_possible_directions(&self, current: &Location) -> Vec<(Location, Direction)> {
-        let mut directions = vec![];
-
-        let north = current.north();
-        if north.0.y != current.y
-            && self.is_valid_incoming_direction(self.get_pipe(&north.0), Direction::North)
-        {
-            directions.push(north);
-        }
-        let west = current.west();
-        if west.0.x != current.x
-            && self.is_valid_incoming_direction(self.get_pipe(&west.0), Direction::West)
-        {
-            directions.push(west);
-        }
-        let east = current.east(self.max_x());
-        if east.0.x <= self.max_x()
-            && self.is_valid_incoming_direction(self.get_pipe(&east.0), Direction::East)
-        {
-            directions.push(east);
-        }
-        let south = current.south(self.max_y());
-        if south.0.y <= self.max_y()
-            && self.is_valid_incoming_direction(self.get_pipe(&south.0), Direction::South)
-        {
-            directions.push(south);
-        }
-
-        directions
+    fn find_possible_directions(&self, current: &Point) -> Vec<(Point, Direction)> {
+        [Direction::North, Direction::West, Direction::East, Direction::South]
+            .into_iter()
+            .filter_map(|direction| self.step(current, direction))
+            .filter(|(next, direction)| self.is_valid_incoming_direction(self.grid[*next], *direction))
+            .collect()
     }
 
     fn is_valid_incoming_direction(&self, c: char, direction: Direction) -> bool {
@@ -343,25 +202,143 @@ impl PipeGrid {
     }
 }
 
-impl Day10 {}
-
 impl Solution for Day10 {
-    fn problem1(path: &str) -> std::io::Result<()> {
-        let grid = PipeGrid::parse(path)?;
+    const DAY: u32 = 10;
+    const TITLE: &'static str = "Pipe Maze";
+
+    type Parsed = PipeGrid;
+
+    fn parse(input: &Input) -> Result<PipeGrid> {
+        PipeGrid::parse(input)
+    }
+
+    fn part1(input: &Input, grid: &PipeGrid) -> Result<Answer> {
+        let path = input.name();
         let start = grid.start;
-        let loop_path = get_loop(&grid, start);
+        let loop_path = get_loop(path, grid, start)?;
         let max = loop_path.len() / 2;
 
-        println!("Found answer to Day10 Problem 1: {}", max);
-        Ok(())
+        Ok(max.into())
     }
 
-    fn problem2(path: &str) -> std::io::Result<()> {
-        let grid = PipeGrid::parse(path)?;
+    fn part2(input: &Input, grid: &PipeGrid) -> Result<Answer> {
+        let path = input.name();
         let start = grid.start;
-        let loop_path = get_loop(&grid, start);
+        let loop_path = get_loop(path, grid, start)?;
         let area = get_loop_area(grid, loop_path, start);
-        println!("Found answer to Day10 Problem 2: {}", area);
-        Ok(())
+        Ok(area.into())
+    }
+
+    fn generate(rng: &mut Rng, size: usize) -> String {
+        let side = size.max(2);
+
+        // Grow a blob of cells without holes or cells that only touch at a corner,
+        // then draw the loop around it at twice the scale so that it never touches
+        // itself
+        let mut blob = Grid::filled(side, side, false);
+        blob[Point::new(rng.usize(0..=side - 1), rng.usize(0..=side - 1))] = true;
+        let mut cells = 1;
+        for _ in 0..side * side * 20 {
+            if cells >= side * side / 2 {
+                break;
+            }
+            let p = Point::new(rng.usize(0..=side - 1), rng.usize(0..=side - 1));
+            if blob[p] || !blob.neighbors4(p).any(|n| blob[n]) {
+                continue;
+            }
+            blob[p] = true;
+            if touches_corner(&blob, p) || has_hole(&blob) {
+                blob[p] = false;
+            } else {
+                cells += 1;
+            }
+        }
+
+        let mut inside = Grid::filled(2 * side + 1, 2 * side + 1, false);
+        for (p, _) in blob.iter().filter(|(_, b)| **b) {
+            for (dx, dy) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+                let right = blob.offset(p, 1, 0).is_some_and(|n| blob[n]);
+                let down = blob.offset(p, 0, 1).is_some_and(|n| blob[n]);
+                let diagonal = blob.offset(p, 1, 1).is_some_and(|n| blob[n]);
+                if (dx == 0 || right) && (dy == 0 || down) && (dx + dy < 2 || diagonal) {
+                    inside[Point::new(2 * p.x + 1 + dx, 2 * p.y + 1 + dy)] = true;
+                }
+            }
+        }
+
+        let on_loop = |p: Point| !inside[p] && inside.neighbors8(p).any(|n| inside[n]);
+        let mut grid = Grid::filled(inside.width(), inside.height(), '.');
+        let mut loop_tiles = vec![];
+        for (p, _) in inside.iter() {
+            if !on_loop(p) {
+                grid[p] = *rng.pick(&['.', '.', '|', '-', 'L', 'J', '7', 'F']);
+                continue;
+            }
+            let connects = |d: Direction| inside.step(p, d).is_some_and(on_loop);
+            grid[p] = match Direction::iter().filter(|d| connects(*d)).collect::<Vec<_>>()[..] {
+                [Direction::North, Direction::South] => '|',
+                [Direction::East, Direction::West] => '-',
+                [Direction::North, Direction::East] => 'L',
+                [Direction::North, Direction::West] => 'J',
+                [Direction::South, Direction::West] => '7',
+                [Direction::East, Direction::South] => 'F',
+                _ => unreachable!("the loop around the blob only ever turns or goes straight"),
+            };
+            loop_tiles.push(p);
+        }
+
+        // nothing but the loop may connect to the start
+        let start = *rng.pick(&loop_tiles);
+        grid[start] = 'S';
+        for n in grid.neighbors4(start).collect::<Vec<_>>() {
+            if !on_loop(n) {
+                grid[n] = '.';
+            }
+        }
+
+        grid.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "\
+-L|F7
+7S-7|
+L|7||
+-L-J|
+L|-JF
+";
+
+    const ENCLOSED: &str = "\
+...........
+.S-------7.
+.|F-----7|.
+.||.....||.
+.||.....||.
+.|L-7.F-J|.
+.|..|.|..|.
+.L--J.L--J.
+...........
+";
+
+    #[test]
+    fn part1_example() {
+        let input = Input::text("example", EXAMPLE);
+        assert_eq!(Day10::problem1(&input).unwrap(), Answer::Int(4));
+    }
+
+    #[test]
+    fn part2_example() {
+        let input = Input::text("example", EXAMPLE);
+        assert_eq!(Day10::problem2(&input).unwrap(), Answer::Int(1));
+    }
+
+    #[test]
+    fn part2_enclosed() {
+        let input = Input::text("example", ENCLOSED);
+        assert_eq!(Day10::problem2(&input).unwrap(), Answer::Int(4));
     }
 }
@@ -1,65 +1,58 @@
+use super::answer::Answer;
 use super::solution::Solution;
+use crate::error::Result;
+use crate::generate::{self, Rng};
+use crate::input::Input;
+use crate::grid::{Grid, Point};
 use std::collections::HashSet;
-use std::fs::File;
-use std::io::{prelude::*, BufReader, Result};
 
 pub struct Day11;
 
 impl Day11 {
-    fn parse(path: &str) -> Result<Vec<Vec<char>>> {
-        let file = File::open(path)?;
-        let reader = BufReader::new(file);
-        let mut layers: Vec<Vec<char>> = vec![];
-
-        for line in reader.lines() {
-            let text = line?;
-            layers.push(text.chars().collect());
-        }
-
-        Ok(layers)
-    }
-
-    fn find_galaxies(space: &[Vec<char>], expansion: usize) -> Vec<(i32, i32)> {
+    fn find_galaxies(space: &Grid<char>, expansion: usize) -> Vec<Point> {
         let (empty_rows, empty_cols) = Day11::find_empty_space(space);
 
-        let mut galaxies = vec![];
-        for i in 0..space.len() {
-            let i_plus = empty_rows.iter().filter(|row| **row < i).count() * expansion;
-            for j in 0..space[0].len() {
-                let j_plus = empty_cols.iter().filter(|col| **col < j).count() * expansion;
-                if space[i][j] == '#' {
-                    galaxies.push(((i + i_plus) as i32, (j + j_plus) as i32))
-                }
-            }
-        }
-        galaxies
+        space
+            .iter()
+            .filter(|(_, c)| **c == '#')
+            .map(|(p, _)| {
+                let y_plus = empty_rows.iter().filter(|row| **row < p.y).count() * expansion;
+                let x_plus = empty_cols.iter().filter(|col| **col < p.x).count() * expansion;
+                Point::new(p.x + x_plus, p.y + y_plus)
+            })
+            .collect()
     }
 
-    fn find_empty_space(space: &[Vec<char>]) -> (Vec<usize>, Vec<usize>) {
-        let mut rows = vec![];
-        let mut columns = vec![];
-
-        for (i, row) in space.iter().enumerate() {
-            if row.iter().all(|c| *c != '#') {
-                rows.push(i);
-            }
-        }
+    fn find_empty_space(space: &Grid<char>) -> (Vec<usize>, Vec<usize>) {
+        let rows = space
+            .rows()
+            .enumerate()
+            .filter(|(_, row)| row.iter().all(|c| *c != '#'))
+            .map(|(i, _)| i)
+            .collect();
 
-        for j in 0..space[0].len() {
-            let col = space.iter().map(|row| row[j]).collect::<Vec<char>>();
-            if col.iter().all(|c| *c != '#') {
-                columns.push(j);
-            }
-        }
+        let columns = space
+            .columns()
+            .enumerate()
+            .filter_map(|(j, mut col)| col.all(|c| *c != '#').then_some(j))
+            .collect();
 
         (rows, columns)
     }
 }
 
 impl Solution for Day11 {
-    fn problem1(path: &str) -> std::io::Result<()> {
-        let space = Day11::parse(path)?;
-        let galaxies = Day11::find_galaxies(&space, 1);
+    const DAY: u32 = 11;
+    const TITLE: &'static str = "Cosmic Expansion";
+
+    type Parsed = Grid<char>;
+
+    fn parse(input: &Input) -> Result<Grid<char>> {
+        Day11::read_input_into_grid(input)
+    }
+
+    fn part1(_input: &Input, space: &Grid<char>) -> Result<Answer> {
+        let galaxies = Day11::find_galaxies(space, 1);
 
         let mut pairs = HashSet::new();
         for (i, g0) in galaxies.iter().enumerate() {
@@ -72,18 +65,14 @@ impl Solution for Day11 {
 
         let mut total = 0;
         for (g0, g1) in pairs.iter() {
-            let diff = g1.0.abs_diff(g0.0) + g1.1.abs_diff(g0.1);
-            total += diff;
+            total += g0.manhattan_distance(g1);
         }
 
-        println!("Got result for Problem 1 day 11: {}", total);
-
-        Ok(())
+        Ok(total.into())
     }
 
-    fn problem2(path: &str) -> std::io::Result<()> {
-        let space = Day11::parse(path)?;
-        let galaxies = Day11::find_galaxies(&space, 999999);
+    fn part2(_input: &Input, space: &Grid<char>) -> Result<Answer> {
+        let galaxies = Day11::find_galaxies(space, 999999);
 
         let mut pairs = HashSet::new();
         for (i, g0) in galaxies.iter().enumerate() {
@@ -96,11 +85,42 @@ impl Solution for Day11 {
 
         let mut total: u64 = 0;
         for (g0, g1) in pairs.iter() {
-            let diff = g1.0.abs_diff(g0.0) + g1.1.abs_diff(g0.1);
-            total += diff as u64;
+            total += g0.manhattan_distance(g1) as u64;
         }
-        println!("Got result for Problem 2 day 11: {}", total);
+        Ok(total.into())
+    }
+
+    fn generate(rng: &mut Rng, size: usize) -> String {
+        generate::grid(rng, size, size, |rng| if rng.chance(0.1) { '#' } else { '.' })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "\
+...#......
+.......#..
+#.........
+..........
+......#...
+.#........
+.........#
+..........
+.......#..
+#...#.....
+";
+
+    #[test]
+    fn part1_example() {
+        let input = Input::text("example", EXAMPLE);
+        assert_eq!(Day11::problem1(&input).unwrap(), Answer::Int(374));
+    }
 
-        Ok(())
+    #[test]
+    fn part2_example() {
+        let input = Input::text("example", EXAMPLE);
+        assert_eq!(Day11::problem2(&input).unwrap(), Answer::Int(82000210));
     }
 }
@@ -1,13 +1,18 @@
+use super::answer::Answer;
 use super::solution::Solution;
 use lazy_static::lazy_static;
 use regex::Regex;
-use std::ascii::AsciiExt;
-use std::collections::{VecDeque, HashMap};
-use std::fs::File;
-use std::thread::{spawn, sleep, self};
+use std::collections::VecDeque;
+use std::num::NonZeroUsize;
+use std::panic;
+use std::thread::{sleep, self};
 use std::time::Duration;
-use std::sync::{mpsc::{Receiver, Sender, channel}, Arc, Mutex};
-use std::io::{prelude::*, BufReader, Result};
+use std::sync::{mpsc::{Receiver, Sender, TryRecvError, channel}, Arc, Mutex};
+use crate::error::{Error, InputLine, Result};
+use crate::runner;
+use crate::generate::Rng;
+use crate::input::Input;
+use crate::{debug, trace};
 
 lazy_static! {
     static ref LINE_RE: Regex = Regex::new(r"(?P<row>[\.#\?]+) (?P<numbers>[\d,]+)").unwrap();
@@ -23,34 +28,36 @@ enum Event {
         original: String,
     },
     Stop,
-    Result{total: u64, row: String},
+    /// The arrangements of a row, or why the worker failed to count them
+    Result{total: std::result::Result<u64, String>, row: String},
 }
 
 pub struct Day12;
 
 impl Day12 {
-    fn parse(path: &str) -> Result<Vec<(String, Vec<i32>)>> {
-        let file = File::open(path)?;
-        let reader = BufReader::new(file);
+    fn parse_rows(input: &Input) -> Result<Vec<(String, Vec<i32>)>> {
+        let path = input.name();
         let mut rows = vec![];
-        for line in reader.lines() {
+        for (i, line) in Day12::input_lines(input)?.enumerate() {
             let text = line?;
-            let matches = LINE_RE.captures(&text).unwrap();
-            let row = matches.name("row").unwrap().as_str().to_owned();
-            let numbers: Vec<i32> = matches
+            let input_line = InputLine::new(path, i, &text);
+            let Some(matches) = LINE_RE.captures(&text) else {
+                return Err(input_line.error("expected `<springs> <group sizes>`"));
+            };
+            let row = matches["row"].to_owned();
+            let numbers = matches
                 .name("numbers")
                 .unwrap()
                 .as_str()
                 .split(',')
-                .map(|n| n.parse::<i32>().unwrap())
-                .collect();
+                .map(|n| input_line.number::<i32>(n))
+                .collect::<Result<Vec<i32>>>()?;
             rows.push((row, numbers));
         }
         Ok(rows)
     }
 
-    fn parse_with_expansion(path: &str) -> Result<Vec<(String, String, Vec<i32>)>> {
-        let data = Day12::parse(path)?;
+    fn expand(data: &[(String, Vec<i32>)]) -> Vec<(String, String, Vec<i32>)> {
         let mut expanded = vec![];
 
         for (row, spec) in data {
@@ -64,7 +71,7 @@ impl Day12 {
             ));
         }
 
-        Ok(expanded)
+        expanded
     }
 
     fn spec_to_groups(row: &str, spec: &[i32]) -> VecDeque<String> {
@@ -73,11 +80,10 @@ impl Day12 {
         let starting_dots = row.chars().take_while(|c| *c == '.').count();
         let ending_dots = row.chars().rev().take_while(|c| *c == '.').count();
         for (i, s) in spec.iter().enumerate() {
-            match i {
-                0  => groups.push_back([".".repeat(starting_dots), "#".repeat(*s as usize), ".".to_string()].join("")),
-                l if l == max => groups.push_back([".", &"#".repeat(*s as usize), &".".repeat(ending_dots)].join("")),
-                _ => groups.push_back([".", &"#".repeat(*s as usize), "."].join("")),
-            }
+            // a row with a single group has both the starting and the ending dots
+            let before = if i == 0 { ".".repeat(starting_dots) } else { ".".to_string() };
+            let after = if i == max { ".".repeat(ending_dots) } else { ".".to_string() };
+            groups.push_back([before, "#".repeat(*s as usize), after].join(""));
         }
 
         groups
@@ -89,25 +95,23 @@ impl Day12 {
         mut row_ptr: usize,
         original: &[i32],
     ) -> usize {
-        // eprintln!("-------------------------");
-        // eprintln!(
-        //     "get_all_combinations called with: row: {:?} and ptr: {}, row_len: {}",
-        //     String::from_iter(row),
-        //     row_ptr,
-        //     row.len()
-        // );
-        // eprintln!("-------------------------");
+        trace!(
+            "get_all_combinations called with: row: {:?} and ptr: {}, row_len: {}",
+            String::from_iter(row),
+            row_ptr,
+            row.len()
+        );
         if groups.is_empty() {
             let str_row = String::from_iter(row).replace('?', ".");
             if Day12::matches_row(&str_row, original) {
-                // println!(
-                //     "Got row: {:?}, row_ptr: {}, returning 1",
-                //     String::from_iter(row),
-                //     row_ptr
-                // );
+                trace!(
+                    "Got row: {:?}, row_ptr: {}, returning 1",
+                    String::from_iter(row),
+                    row_ptr
+                );
                 return 1;
             } else {
-                // eprintln!("got invalid row {} for spec: {:?}. returning 0", String::from_iter(row), original);
+                trace!("got invalid row {} for spec: {:?}. returning 0", String::from_iter(row), original);
                 return 0
             }
         }
@@ -122,7 +126,7 @@ impl Day12 {
                 .collect::<String>()
                 .replace('?', ".");
             altered_row.splice(0..row_ptr, fill.chars().collect::<Vec<char>>());
-            while row_ptr < (row.len() - group.len() - 1)
+            while row_ptr + group.len() + 1 < row.len()
                 && !Day12::validate(&altered_row[..row_ptr + group.len()], &group, row_ptr)
             {
                 if altered_row[row_ptr] == '?' {
@@ -131,20 +135,20 @@ impl Day12 {
                 row_ptr += 1;
             }
 
-            if row_ptr > row.len() - group.len() {
-                // eprintln!("Breaking early: row_ptr: {}, group_len: {}, row_len: {}", row_ptr, group.len(), row_ptr);
+            if row_ptr + group.len() > row.len() {
+                trace!("Breaking early: row_ptr: {}, group_len: {}, row_len: {}", row_ptr, group.len(), row.len());
                 break;
             }
 
             if Day12::validate(&altered_row[..row_ptr + group.len()], &group, row_ptr) {
-                // eprintln!("Altering row: {:?}, range: {:?} with {:?}", String::from_iter(&altered_row), row_ptr..row_ptr + group.len(), String::from_iter(group.clone()));
+                trace!("Altering row: {:?}, range: {:?} with {:?}", String::from_iter(&altered_row), row_ptr..row_ptr + group.len(), String::from_iter(&group));
                 altered_row.splice(row_ptr..row_ptr + group.len(), group.clone());
-                // eprintln!(
-                //     "making recursive call with: {:?}, {:?}, {}",
-                //     String::from_iter(&altered_row),
-                //     groups.clone(),
-                //     row_ptr + group.len() - 1
-                // );
+                trace!(
+                    "making recursive call with: {:?}, {:?}, {}",
+                    String::from_iter(&altered_row),
+                    groups,
+                    row_ptr + group.len() - 1
+                );
                 total += Day12::get_all_combinations_2(
                     &altered_row,
                     groups.clone(),
@@ -152,20 +156,20 @@ impl Day12 {
                     original,
                 );
             }
-            // eprintln!(
-            //     "Returned from recursive call -> row: {:?}, row_ptr: {}, group: {:?}",
-            //     String::from_iter(&altered_row),
-            //     row_ptr,
-            //     String::from_iter(&group)
-            // );
+            trace!(
+                "Returned from recursive call -> row: {:?}, row_ptr: {}, group: {:?}",
+                String::from_iter(&altered_row),
+                row_ptr,
+                String::from_iter(&group)
+            );
             row_ptr += 1;
-            if row_ptr > row.len() - group.len() {
-                // eprintln!("Breaking at loop end: row_ptr: {}, group_len: {}, row_len: {}", row_ptr, group.len(), row.len());
+            if row_ptr + group.len() > row.len() {
+                trace!("Breaking at loop end: row_ptr: {}, group_len: {}, row_len: {}", row_ptr, group.len(), row.len());
                 break;
             }
         }
 
-        // eprintln!("returning total: {}", total);
+        trace!("returning total: {}", total);
         total
     }
 
@@ -199,10 +203,20 @@ impl Day12 {
         }
     }
 
+    /// Part 1 by trying both springs for every unknown one, which takes a while on
+    /// rows with many of them
+    pub fn part1_brute(input: &Input) -> Result<Answer> {
+        let total: usize = Day12::parse_rows(input)?
+            .iter()
+            .map(|(row, spec)| Day12::get_all_combinations(row, spec))
+            .sum();
+        Ok(total.into())
+    }
+
     fn matches_row(row: &str, spec: &[i32]) -> bool {
-        // eprintln!("Checking: {}", row);
+        trace!("Checking: {}", row);
         let groups = row
-            .split(|c| c == '.')
+            .split('.')
             .filter(|s| !s.is_empty())
             .collect::<Vec<&str>>();
         if groups.len() != spec.len() {
@@ -216,55 +230,117 @@ impl Day12 {
             == spec.len()
     }
 
+    /// Hand the rows out to the workers, keeping at most one row per worker waiting,
+    /// and add up the arrangements they send back
+    fn collect_combinations(
+        path: &str,
+        rows: Vec<(String, String, Vec<i32>)>,
+        workers: usize,
+        mtx: &Sender<Event>,
+        mrx: &Receiver<Event>,
+    ) -> Result<u64> {
+        let gone = || Error::no_solution(path, "every worker stopped before the rows were counted");
+        let receive = || match mrx.recv() {
+            Ok(Event::Result { total: Ok(total), row }) => {
+                debug!("Got result for {}: {}", row, total);
+                Ok(total)
+            }
+            Ok(Event::Result { total: Err(e), row }) => {
+                Err(Error::no_solution(path, format!("failed to count the row `{}`: {}", row, e)))
+            }
+            Ok(_) => Err(Error::no_solution(path, "a worker replied with something other than a result")),
+            Err(_) => Err(gone()),
+        };
+
+        let mut full_total = 0;
+        let mut sent = 0;
+        let mut recved = 0;
+        let row_count = rows.len();
+        for (original, row, spec) in rows {
+            if sent == workers {
+                full_total += receive()?;
+                recved += 1;
+                sent -= 1;
+            }
+            let groups = Day12::spec_to_groups(&row, &spec);
+            mtx.send(Event::Work { row, groups, spec, original }).map_err(|_| gone())?;
+            sent += 1;
+        }
+
+        while recved < row_count {
+            full_total += receive()?;
+            recved += 1;
+        }
+
+        Ok(full_total)
+    }
+
+    /// Work through rows until told to stop, or until there's no one left to send
+    /// rows to this worker
     fn parrallel_find_combinations(sender: Sender<Event>, reciever: Arc<Mutex<Receiver<Event>>>) {
         loop {
             let some_event = match reciever.try_lock().ok() {
                 Some(rx) => {
-                    rx.try_recv().ok()
+                    rx.try_recv()
                 },
-                _ => None
+                _ => Err(TryRecvError::Empty)
             };
 
-            if let Some(Event::Work{row, groups, spec, original}) = some_event {
-                let total = Day12::get_all_combinations_2(&row.chars().collect::<Vec<char>>(), groups, 0, &spec) as u64;
-                sender.send(Event::Result{total, row: original}).unwrap();
-            } else {
-                sleep(Duration::from_millis(1));
+            match some_event {
+                Ok(Event::Work{row, groups, spec, original}) => {
+                    // a panic is sent back like any other result, so the rows that are
+                    // still waiting on this worker don't wait forever
+                    let total = runner::catch(|| {
+                        Ok(Day12::get_all_combinations_2(&row.chars().collect::<Vec<char>>(), groups, 0, &spec) as u64)
+                    });
+                    if sender.send(Event::Result{total, row: original}).is_err() {
+                        break;
+                    }
+                },
+                Ok(Event::Stop) | Err(TryRecvError::Disconnected) => break,
+                Ok(Event::Result{..}) | Err(TryRecvError::Empty) => sleep(Duration::from_millis(1)),
             }
         }
     }
 }
 
 impl Solution for Day12 {
-    fn problem1(path: &str) -> std::io::Result<()> {
-        let rows = Day12::parse(path)?;
+    const DAY: u32 = 12;
+    const TITLE: &'static str = "Hot Springs";
+
+    type Parsed = Vec<(String, Vec<i32>)>;
+
+    fn parse(input: &Input) -> Result<Vec<(String, Vec<i32>)>> {
+        Day12::parse_rows(input)
+    }
+
+    fn part1(_input: &Input, rows: &Vec<(String, Vec<i32>)>) -> Result<Answer> {
         let mut total = 0;
         for (row, spec) in rows {
-            let groups = Day12::spec_to_groups(&row, &spec);
-            // eprintln!("Checking groups: {:?} against: {:?}", groups, row);
+            let groups = Day12::spec_to_groups(row, spec);
+            debug!("Checking groups: {:?} against: {:?}", groups, row);
             let row_total =
-                Day12::get_all_combinations_2(&row.chars().collect::<Vec<char>>(), groups, 0, &spec);
+                Day12::get_all_combinations_2(&row.chars().collect::<Vec<char>>(), groups, 0, spec);
             total += row_total;
 
         }
-        println!("Got answer for Day12 problem1: {}", total);
-        Ok(())
+        Ok(total.into())
     }
 
-    fn problem2(path: &str) -> std::io::Result<()> {
-        let rows = Day12::parse_with_expansion(path)?;
-        let workers = 11;
+    fn part2(input: &Input, rows: &Vec<(String, Vec<i32>)>) -> Result<Answer> {
+        let rows = Day12::expand(rows);
+        let workers = thread::available_parallelism().map_or(1, NonZeroUsize::get);
         let (wtx, mrx) =  channel();
         let (mtx, wrx) = channel();
         let cloneable_wrx = Arc::new(Mutex::new(wrx));
 
         if rows.len() == 1 {
-            eprintln!("Processing single record");
+            debug!("Processing single record");
             let (original, row, spec) = rows[0].clone();
             let groups = Day12::spec_to_groups(&row, &spec);
             let total = Day12::get_all_combinations_2(&row.chars().collect::<Vec<char>>(), groups, 0, &spec);
-            eprintln!("Got result for {}, {}", original, total);
-            return Ok(());
+            debug!("Got result for {}, {}", original, total);
+            return Ok(total.into());
         }
 
         let mut handles = vec![];
@@ -277,47 +353,126 @@ impl Solution for Day12 {
             });
             handles.push(handle);
         }
+        // only the workers can send now, so the replies stop once every worker has gone
+        drop(wtx);
 
-        let mut full_total = 0;
-        let mut sent = 0;
-        let mut recved = 0;
-        let row_count = rows.len();
-        for (original, row, spec) in rows {
-            if sent == workers {
-                match mrx.recv() {
-                    Ok(Event::Result { total, row }) => {
-                        eprintln!("Got result for {}, {}", row, total);
-                        recved += 1;
-                        full_total += total;
-                    }
-                    _ => panic!(),
-                }
-                sent -= 1;
+        let full_total = Day12::collect_combinations(input.name(), rows, workers, &mtx, &mrx);
 
+        // workers that have already gone don't need telling
+        for _ in 0..workers {
+            mtx.send(Event::Stop).ok();
+        }
+        for handle in handles {
+            if let Err(payload) = handle.join() {
+                panic::resume_unwind(payload);
             }
-            let groups = Day12::spec_to_groups(&row, &spec);
-            mtx.send(Event::Work { row, groups, spec, original }).unwrap();
-            sent += 1;
         }
 
+        Ok(full_total?.into())
+    }
 
-        while recved < row_count {
-            match mrx.recv() {
-                Ok(Event::Result { total, row }) => {
-                    eprintln!("Got result for {}: {}", row, total);
-                    recved += 1;
-                    full_total += total;
+    fn generate(rng: &mut Rng, size: usize) -> String {
+        let mut out = String::new();
+        for _ in 0..size {
+            // start from a known arrangement so that every row has at least one, then
+            // hide some of the springs
+            let springs = loop {
+                let springs = rng.string(".#", 4..=12);
+                if springs.contains('#') {
+                    break springs;
                 }
-                _ => panic!(),
-            }
-
+            };
+            let spec: Vec<String> = GROUP_RE.find_iter(&springs).map(|m| m.len().to_string()).collect();
+            let row: String = springs.chars().map(|c| if rng.chance(0.4) { '?' } else { c }).collect();
+            out += &format!("{} {}\n", row, spec.join(","));
         }
-        println!("Got answer for Day12 problem 2: {}", full_total);
+        out
+    }
+}
 
-        for _ in 0..workers {
-            mtx.send(Event::Stop).unwrap();
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "\
+???.### 1,1,3
+.??..??...?##. 1,1,3
+?#?#?#?#?#?#?#? 1,3,1,6
+????.#...#... 4,1,1
+????.######..#####. 1,6,5
+?###???????? 3,2,1
+";
+
+    #[test]
+    fn part1_example() {
+        let input = Input::text("example", EXAMPLE);
+        assert_eq!(Day12::problem1(&input).unwrap(), Answer::Int(21));
+    }
+
+    #[test]
+    fn part2_unfolds_rows() {
+        // every row of the example takes minutes in a debug build, so this only checks
+        // that rows are unfolded and summed across the workers
+        let input = Input::text("example", "#.# 1,1\n.##. 2\n");
+        assert_eq!(Day12::problem2(&input).unwrap(), Answer::Int(2));
+    }
+
+    #[test]
+    #[ignore = "slow, run with --release --ignored"]
+    fn part2_example() {
+        let input = Input::text("example", EXAMPLE);
+        assert_eq!(Day12::problem2(&input).unwrap(), Answer::Int(525152));
+    }
+
+    #[test]
+    fn groups_that_fill_the_row() {
+        for (row, arrangements) in [("#### 4", 1), ("? 1", 1), ("?? 1", 2), ("??? 1,1", 1), (".?# 1", 1), ("#?? 2", 1)] {
+            let input = Input::text("row", &format!("{}\n", row));
+            assert_eq!(Day12::problem1(&input).unwrap(), Answer::Int(arrangements), "{}", row);
+            assert_eq!(Day12::part1_brute(&input).unwrap(), Answer::Int(arrangements), "{}", row);
         }
+    }
+
+    #[test]
+    fn part2_of_a_single_spring() {
+        // unfolded to `?????????` with five groups of one, which only fit one way
+        let input = Input::text("row", "? 1\n");
+        assert_eq!(Day12::problem2(&input).unwrap(), Answer::Int(1));
+    }
+
+    fn rows(count: usize) -> Vec<(String, String, Vec<i32>)> {
+        (0..count).map(|_| ("#".to_owned(), "#".to_owned(), vec![1])).collect()
+    }
+
+    #[test]
+    fn failed_rows_are_an_error() {
+        let (wtx, mrx) = channel();
+        let (mtx, wrx) = channel();
+        let worker = thread::spawn(move || {
+            while let Ok(Event::Work { original, .. }) = wrx.recv() {
+                let total = if original == "#" { Err("panicked: boom".to_owned()) } else { Ok(1) };
+                wtx.send(Event::Result { total, row: original }).unwrap();
+            }
+        });
+
+        let err = Day12::collect_combinations("test", rows(3), 1, &mtx, &mrx).unwrap_err();
+        assert!(err.to_string().contains("failed to count the row `#`: panicked: boom"));
+        drop(mtx);
+        worker.join().unwrap();
+    }
 
-        Ok(())
+    #[test]
+    fn workers_that_are_gone_are_an_error() {
+        let (wtx, mrx) = channel();
+        let (mtx, wrx) = channel::<Event>();
+        // a worker that takes a row and goes away without replying
+        let worker = thread::spawn(move || {
+            let _row = wrx.recv();
+            drop(wtx);
+        });
+
+        let err = Day12::collect_combinations("test", rows(2), 2, &mtx, &mrx).unwrap_err();
+        assert!(matches!(err, Error::NoSolution { .. }));
+        worker.join().unwrap();
     }
 }
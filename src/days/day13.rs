@@ -1,7 +1,10 @@
+use super::answer::Answer;
 use super::solution::Solution;
 use std::collections::HashMap;
-use std::fs::File;
-use std::io::{prelude::*, Result, BufReader};
+use crate::error::{Error, InputLine, Result};
+use crate::generate::{self, Rng};
+use crate::input::Input;
+use crate::grid::{Grid, Point};
 
 pub struct Day13;
 
@@ -11,80 +14,45 @@ enum Type {
 }
 
 impl Day13 {
-    fn parse_vert(path: &str) -> Result<Vec<Vec<String>>> {
-        let file = File::open(path)?;
-        let reader = BufReader::new(file);
-        let mut all = vec![];
-        let mut mirror = vec![];
-        for line in reader.lines() {
-            let text = line?;
-            if text.chars().all(|c| c.is_whitespace()) && !mirror.is_empty() {
-                if !mirror.is_empty() {
-                    all.push(mirror.clone());
-                    mirror.clear();
-                }
-                continue;
-            }
-            for (i, c) in text.chars().enumerate() {
-                if mirror.len() == i {
-                    mirror.push(String::from(c));
-                } else {
-                    mirror[i].push(c);
-                }
-            }
-        }
-
-        all.push(mirror);
-        Ok(all)
-    }
-
-    fn flip_char(mut chars: Vec<String>, i: usize, j: usize) -> Vec<String> {
-        let new_char = match chars.get(i).unwrap().chars().nth(j) {
-            Some('.') => {
-               '#'
-            }
-            Some('#') => {
-                '.'
-            }
-            c => {
-                eprintln!("Got bad char: {:?}", c);
-                unreachable!()
-            }
+    fn flip_char(mut mirror: Grid<char>, p: Point) -> Grid<char> {
+        mirror[p] = match mirror[p] {
+            '.' => '#',
+            '#' => '.',
+            c => unreachable!("Got bad char: {:?}", c),
         };
-        let s = chars[i].clone();
-        let new_s: String = s.chars().enumerate().map(|(i,c)| if i == j { new_char } else { c }).collect();
-        chars[i] = new_s;
-        chars
+        mirror
     }
 
-    fn parse_horizontal(path: &str) -> Result<Vec<Vec<String>>> {
-        let file = File::open(path)?;
-        let reader = BufReader::new(file);
+    fn parse_horizontal(input: &Input) -> Result<Vec<Grid<char>>> {
+        let path = input.name();
         let mut all = vec![];
-        let mut mirror = vec![];
-        for line in reader.lines() {
-            let text = line?;
-            if text.chars().all(|c| c.is_whitespace()) {
-                if !mirror.is_empty() {
-                    all.push(mirror.clone());
-                    mirror.clear();
+        for block in Day13::input_blocks(input)? {
+            let block = block?;
+            let mut rows: Vec<Vec<char>> = vec![];
+            for (i, text) in block.numbered() {
+                let input_line = InputLine::new(path, i, text);
+                if let Some((j, c)) = text.char_indices().find(|(_, c)| !c.is_whitespace() && !".#".contains(*c)) {
+                    return Err(input_line.error_at(&text[j..j + c.len_utf8()], "expected `.` or `#`"));
                 }
-                continue;
+                let row: Vec<char> = text.trim().chars().collect();
+                if rows.first().is_some_and(|first| first.len() != row.len()) {
+                    return Err(input_line.error("row length differs from the first row of the pattern"));
+                }
+                rows.push(row);
             }
-            mirror.push(text);
+            all.push(Grid::new(rows[0].len(), rows.len(), rows.concat()));
         }
-        all.push(mirror);
         Ok(all)
     }
 
-    fn find_sym(mirror: &[String], reject: usize) -> usize {
+    fn find_sym(mirror: &Grid<char>, reject: usize) -> usize {
         let mut ptr = 1;
-        while ptr < mirror.len() {
+        while ptr < mirror.height() {
             let mut left = ptr -1;
             let mut right =  ptr;
             let mut found_sym = false;
             loop {
-                if mirror[left] == mirror[right] {
+                if mirror.row(left) == mirror.row(right) {
                     found_sym = true;
                 } else {
                     if found_sym {
@@ -93,7 +61,7 @@ impl Day13 {
                     }
                     break;
                 }
-                if left > 0 && right < mirror.len() - 1 {
+                if left > 0 && right < mirror.height() - 1 {
                    left -= 1;
                    right += 1;
                 } else {
@@ -101,7 +69,7 @@ impl Day13 {
                 }
             }
 
-            if found_sym && (left == 0 || right == mirror.len() - 1) && ptr != reject  {
+            if found_sym && (left == 0 || right == mirror.height() - 1) && ptr != reject  {
                 return ptr;
             } else {
                 ptr += 1;
@@ -114,9 +82,18 @@ impl Day13 {
 
 
 impl Solution for Day13 {
-    fn problem1(path: &str) -> Result<()> {
-        let horiz = Day13::parse_horizontal(path)?;
-        let vert = Day13::parse_vert(path)?;
+    const DAY: u32 = 13;
+    const TITLE: &'static str = "Point of Incidence";
+
+    type Parsed = Vec<Grid<char>>;
+
+    fn parse(input: &Input) -> Result<Vec<Grid<char>>> {
+        Day13::parse_horizontal(input)
+    }
+
+    fn part1(input: &Input, horiz: &Vec<Grid<char>>) -> Result<Answer> {
+        let path = input.name();
+        let vert = horiz.iter().map(Grid::transpose).collect::<Vec<_>>();
 
         let mut results: Vec<usize> = vec![];
 
@@ -126,7 +103,7 @@ impl Solution for Day13 {
                 let vert_line = Day13::find_sym(mirror_vert, 0);
 
                 if vert_line == 0 {
-                    panic!("Failed to find any line");
+                    return Err(Error::no_solution(path, "Failed to find any line"));
                 }
 
                 results.push(vert_line);
@@ -136,14 +113,12 @@ impl Solution for Day13 {
         }
 
         let total = results.iter().sum::<usize>();
-        println!("Got answer for day13 problem 1: {}", total);
-
-        Ok(())
+        Ok(total.into())
     }
 
-    fn problem2(path: &str) -> Result<()> {
-        let horiz = Day13::parse_horizontal(path)?;
-        let vert = Day13::parse_vert(path)?;
+    fn part2(input: &Input, horiz: &Vec<Grid<char>>) -> Result<Answer> {
+        let path = input.name();
+        let vert = horiz.iter().map(Grid::transpose).collect::<Vec<_>>();
         let mut results: HashMap<usize, (usize, Type)> = HashMap::new();
         for (k, (mirror_horiz, mirror_vert)) in horiz.iter().zip(vert.iter()).enumerate() {
             let horiz_line = Day13::find_sym(mirror_horiz, 0);
@@ -151,7 +126,7 @@ impl Solution for Day13 {
                 let vert_line = Day13::find_sym(mirror_vert, 0);
 
                 if vert_line == 0 {
-                    panic!("Failed to find any line");
+                    return Err(Error::no_solution(path, "Failed to find any line"));
                 }
 
                 results.insert(k, (vert_line, Type::Vert));
@@ -161,10 +136,10 @@ impl Solution for Day13 {
         }
         let mut new_results = vec![];
         for (k, (mirror_horiz, mirror_vert)) in horiz.iter().zip(vert.iter()).enumerate() {
-            'outer: for i in 0..mirror_horiz.len() {
-                for j in 0..mirror_vert.len() {
-                    let flipped_horiz = Day13::flip_char(mirror_horiz.clone(), i, j);
-                    let flipped_vert = Day13::flip_char(mirror_vert.clone(), j, i);
+            'outer: for i in 0..mirror_horiz.height() {
+                for j in 0..mirror_vert.height() {
+                    let flipped_horiz = Day13::flip_char(mirror_horiz.clone(), Point::new(j, i));
+                    let flipped_vert = Day13::flip_char(mirror_vert.clone(), Point::new(i, j));
                     let (horiz_reject, vert_reject) = match results.get(&k) {
                        Some((n, Type::Vert)) => (0, *n),
                        Some((n, Type::Horiz)) => (*n, 0),
@@ -191,9 +166,69 @@ impl Solution for Day13 {
 
         }
         let total = new_results.iter().sum::<usize>();
-        println!("Got answer for day13 problem 2: {}", total);
+        Ok(total.into())
+    }
 
+    fn generate(rng: &mut Rng, size: usize) -> String {
+        let mut patterns = vec![];
+        for _ in 0..size {
+            let width = rng.usize(5..=13);
+            let height = rng.usize(5..=13);
+            // A vertical line that part 1 can find, which leaves some columns on the right
+            // out of the reflection, and a horizontal line that is one smudge away, with
+            // the smudge in those columns so it doesn't break the vertical line
+            let column = rng.usize(1..=(width - 1) / 2);
+            let row = rng.usize(1..=height - 1);
+            let reflect_x = |x: usize| if (column..2 * column).contains(&x) { 2 * column - 1 - x } else { x };
+            let reflect_y = |y: usize| if y >= row && y < 2 * row { 2 * row - 1 - y } else { y };
+
+            let base = generate::grid(rng, width, height, |rng| *rng.pick(&['.', '#']));
+            let base: Vec<Vec<char>> = base.lines().map(|l| l.chars().collect()).collect();
+            let mut pattern = Grid::filled(width, height, '.');
+            for p in pattern.points().collect::<Vec<_>>() {
+                pattern[p] = base[reflect_y(p.y)][reflect_x(p.x)];
+            }
+
+            let mirrored = row.saturating_sub(height - row)..row;
+            let smudge = Point::new(rng.usize(2 * column..=width - 1), rng.usize(mirrored.start..=mirrored.end - 1));
+            pattern = Day13::flip_char(pattern, smudge);
+            patterns.push(pattern.to_string());
+        }
+        patterns.join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "\
+#.##..##.
+..#.##.#.
+##......#
+##......#
+..#.##.#.
+..##..##.
+#.#.##.#.
+
+#...##..#
+#....#..#
+..##..###
+#####.##.
+#####.##.
+..##..###
+#....#..#
+";
+
+    #[test]
+    fn part1_example() {
+        let input = Input::text("example", EXAMPLE);
+        assert_eq!(Day13::problem1(&input).unwrap(), Answer::Int(405));
+    }
 
-        Ok(())
+    #[test]
+    fn part2_example() {
+        let input = Input::text("example", EXAMPLE);
+        assert_eq!(Day13::problem2(&input).unwrap(), Answer::Int(400));
     }
 }
@@ -1,145 +1,67 @@
+use super::answer::Answer;
 use super::solution::Solution;
 use std::collections::HashMap;
-use std::io::{prelude::*, BufReader, Result};
-use std::fs::File;
-use std::mem::swap;
+use crate::direction::Direction;
+use crate::error::Result;
+use crate::generate::{self, Rng};
+use crate::input::Input;
+use crate::grid::{Grid, Point};
 
 pub struct Day14;
 
-#[derive(Clone, Copy, Debug)]
-enum Direction {
-    North,
-    West,
-    South,
-    East,
-}
-
 impl Day14 {
-    fn parse(path: &str) -> Result<Vec<Vec<char>>> {
-        Ok(
-            BufReader::new(File::open(path)?).lines().map(|line| line.unwrap().chars().collect::<Vec<char>>()).collect()
-        )
-    }
-
-    fn tilt_north(dish: &mut [Vec<char>]) {
-        for i in 0..dish.len() {
-            if i == 0 {
-                continue;
-            }
-            for j in 0..dish[0].len() {
-                match dish[i][j] {
-                    rock @ 'O' => {
-                        for k in (0..i).rev() {
-                            match dish[k][j] {
-                                '.' => {
-                                    dish[k][j] = rock;
-                                    dish[k+1][j] = '.'
-                                },
-                                _ => break,
-                            }
-                        }
-                    },
-                    _ => continue
+    fn tilt_north(dish: &mut Grid<char>) {
+        for y in 1..dish.height() {
+            for x in 0..dish.width() {
+                if dish[Point::new(x, y)] != 'O' {
+                    continue;
                 }
-            }
-        }
-
-    }
-
-    fn tilt_south(dish: &mut [Vec<char>]) {
-        for i in (0..dish.len()).rev() {
-            if i == dish.len() - 1 {
-                continue;
-            }
-            for j in 0..dish[0].len() {
-                match dish[i][j] {
-                    rock @ 'O' => {
-                        for k in i+1..dish.len() {
-                            match dish[k][j] {
-                                '.' => {
-                                    dish[k][j] = rock;
-                                    dish[k-1][j] = '.'
-                                },
-                                _ => break,
-                            }
-                        }
-                    },
-                    _ => continue
+                for k in (0..y).rev() {
+                    match dish[Point::new(x, k)] {
+                        '.' => {
+                            dish[Point::new(x, k)] = 'O';
+                            dish[Point::new(x, k + 1)] = '.'
+                        },
+                        _ => break,
+                    }
                 }
             }
         }
-
     }
 
-    fn tilt_west(dish: &mut [Vec<char>]) {
-        for j in 0..dish[0].len() {
-            if j == 0 {
-                continue;
-            }
-            for i in 0..dish.len() {
-                match dish[i][j] {
-                    rock @ 'O' => {
-                        for k in (0..j).rev() {
-                            match dish[i][k] {
-                                '.' => {
-                                    dish[i][k] = rock;
-                                    dish[i][k+1] = '.'
-                                },
-                                _ => break,
-                            }
-                        }
-                    },
-                    _ => continue
-                }
-            }
-        }
-    }
-
-    fn tilt_east(dish: &mut [Vec<char>]) {
-        for j in (0..dish[0].len()).rev() {
-            if j == dish[0].len() - 1 {
-                continue;
-            }
-            for i in 0..dish.len() {
-                match dish[i][j] {
-                    rock @ 'O' => {
-                        for k in j+1..dish[0].len() {
-                            match dish[i][k] {
-                                '.' => {
-                                    dish[i][k] = rock;
-                                    dish[i][k-1] = '.'
-                                },
-                                _ => break,
-                            }
-                        }
-                    },
-                    _ => continue
-                }
-            }
-        }
-
-    }
-
-    fn tilt(dish: &mut [Vec<char>], direction: Direction) {
+    /// Tilt in any direction by turning the dish so that `direction` faces north
+    fn tilt(dish: &mut Grid<char>, direction: Direction) {
         match direction {
             Direction::North => Day14::tilt_north(dish),
-            Direction::West => Day14::tilt_west(dish),
-            Direction::South => Day14::tilt_south(dish),
-            Direction::East => Day14::tilt_east(dish),
+            Direction::West => {
+                *dish = dish.rotate_clockwise();
+                Day14::tilt_north(dish);
+                *dish = dish.rotate_counter_clockwise();
+            },
+            Direction::South => {
+                *dish = dish.rotate_clockwise().rotate_clockwise();
+                Day14::tilt_north(dish);
+                *dish = dish.rotate_clockwise().rotate_clockwise();
+            },
+            Direction::East => {
+                *dish = dish.rotate_counter_clockwise();
+                Day14::tilt_north(dish);
+                *dish = dish.rotate_clockwise();
+            },
         }
     }
 
-    fn cyle(dish: &mut [Vec<char>]) {
+    fn cyle(dish: &mut Grid<char>) {
         [Direction::North, Direction::West, Direction::South, Direction::East].iter().map(|d| {
             Day14::tilt(dish, *d);
         }).for_each(drop);
     }
 
-    fn count_load(dish: &[Vec<char>]) -> usize {
+    fn count_load(dish: &Grid<char>) -> usize {
         let mut total = 0;
-        let mut multiplier = dish.len();
+        let mut multiplier = dish.height();
 
-        for row in dish {
+        for row in dish.rows() {
             total += row.iter().filter(|c| **c == 'O').count() * multiplier;
             multiplier -= 1;
         }
@@ -151,23 +73,30 @@ impl Day14 {
 
 
 impl Solution for Day14 {
+    const DAY: u32 = 14;
+    const TITLE: &'static str = "Parabolic Reflector Dish";
 
-    fn problem1(path: &str) -> Result<()> {
-        let mut dish = Day14::parse(path)?;
+    type Parsed = Grid<char>;
+
+    fn parse(input: &Input) -> Result<Grid<char>> {
+        Day14::read_input_into_grid(input)
+    }
+
+    fn part1(_input: &Input, dish: &Grid<char>) -> Result<Answer> {
+        let mut dish = dish.clone();
         Day14::tilt(&mut dish, Direction::North);
-        println!("Got answer for Day14 problem 1: {}", Day14::count_load(&dish));
-        Ok(())
+        Ok(Day14::count_load(&dish).into())
     }
 
-    fn problem2(path: &str) -> Result<()> {
-        let mut dish = Day14::parse(path)?;
-        let mut seen: HashMap<String, (usize, usize)> = HashMap::new();
+    fn part2(_input: &Input, dish: &Grid<char>) -> Result<Answer> {
+        let mut dish = dish.clone();
+        let mut seen: HashMap<Grid<char>, (usize, usize)> = HashMap::new();
         let mut cycle = 0;
         let mut original = dish.clone();
 
         loop {
             Day14::cyle(&mut dish);
-            let current =  dish.iter().cloned().map(String::from_iter).collect::<Vec<String>>().join("\n");
+            let current = dish.clone();
             if !seen.contains_key(&current) {
                 seen.insert(current.clone(), (1, cycle));
             } else {
@@ -191,9 +120,40 @@ impl Solution for Day14 {
             Day14::cyle(&mut original);
         }
 
-        println!("Got answer for Day14 problem 2: {}", Day14::count_load(&original));
-        Ok(())
+        Ok(Day14::count_load(&original).into())
+    }
+
+    fn generate(rng: &mut Rng, size: usize) -> String {
+        generate::grid(rng, size, size, |rng| *rng.pick(&['O', '#', '.', '.', '.', '.']))
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "\
+O....#....
+O.OO#....#
+.....##...
+OO.#O....O
+.O.....O#.
+O.#..O.#.#
+..O..#O..O
+.......O..
+#....###..
+#OO..#....
+";
+
+    #[test]
+    fn part1_example() {
+        let input = Input::text("example", EXAMPLE);
+        assert_eq!(Day14::problem1(&input).unwrap(), Answer::Int(136));
+    }
 
+    #[test]
+    fn part2_example() {
+        let input = Input::text("example", EXAMPLE);
+        assert_eq!(Day14::problem2(&input).unwrap(), Answer::Int(64));
+    }
+}
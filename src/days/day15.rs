@@ -1,24 +1,23 @@
+use super::answer::Answer;
 use super::solution::Solution;
-use std::collections::{VecDeque, HashMap};
-use std::fs::File;
-use std::io::{prelude::*, Result, BufReader};
+use std::collections::HashMap;
+use crate::error::{InputLine, Result};
+use crate::generate::Rng;
+use crate::input::Input;
 
 
 pub struct Day15;
 
 
 impl Day15 {
-    fn parse(path: &str) -> Result<Vec<Vec<char>>> {
-        let file =  File::open(path)?;
-        let mut data = String::new();
-        BufReader::new(file).read_to_string(&mut data)?;
-        Ok(data.split(',').map(|s| s.trim().chars().collect()).collect())
+    fn steps(data: &str) -> impl Iterator<Item = &str> {
+        data.split(',').map(|s| s.trim())
     }
 
-    fn hash(chars: Vec<char>) -> u32 {
+    fn hash(step: &str) -> u32 {
         let mut h = 0;
 
-        for c in chars {
+        for c in step.chars() {
             h += c as u32;
             h *= 17;
             h %= 256;
@@ -28,20 +27,21 @@ impl Day15 {
     }
 }
 
-struct LenseBox {
-    lenses: HashMap<String, (u32, u32)>,
+#[derive(Default)]
+pub struct LenseBox {
+    pub lenses: HashMap<String, (u32, u32)>,
     current: u32,
 }
 
 impl LenseBox {
-    fn new() -> Self {
+    pub fn new() -> Self {
         Self {
             lenses: HashMap::new(),
             current: 0,
         }
     }
 
-    fn insert(&mut self, label: &str, focal: u32) {
+    pub fn insert(&mut self, label: &str, focal: u32) {
 
         if self.lenses.contains_key(label) {
             let pos = self.lenses.get(label).unwrap().0;
@@ -53,7 +53,7 @@ impl LenseBox {
 
     }
 
-    fn remove(&mut self, label: &str) {
+    pub fn remove(&mut self, label: &str) {
         if let Some((pos, _)) = self.lenses.remove(label) {
             self.lenses.values_mut().filter(|(p, _)| *p > pos).for_each(|(p, _)| *p -= 1 );
             self.current -= 1;
@@ -63,15 +63,23 @@ impl LenseBox {
 
 
 impl Solution for Day15{
+    const DAY: u32 = 15;
+    const TITLE: &'static str = "Lens Library";
 
-    fn problem1(path: &str) -> std::io::Result<()> {
-        let total = Day15::parse(path)?.into_iter().map(Day15::hash).sum::<u32>();
-        println!("Got answer for Day 15 Problem 1: {}", total);
-        Ok(())
+    type Parsed = String;
+
+    fn parse(input: &Input) -> Result<String> {
+        Day15::read_input_to_string(input)
+    }
+
+    fn part1(_input: &Input, data: &String) -> Result<Answer> {
+        let total = Day15::steps(data).map(Day15::hash).sum::<u32>();
+        Ok(total.into())
     }
 
-    fn problem2(path: &str) -> std::io::Result<()> {
-        let instructions = Day15::parse(path)?;
+    fn part2(input: &Input, data: &String) -> Result<Answer> {
+        let path = input.name();
+        let line = InputLine::new(path, 0, data);
 
         let mut buckets: Vec<LenseBox> = Vec::new();
 
@@ -79,29 +87,61 @@ impl Solution for Day15{
             buckets.push(LenseBox::new());
         }
 
-        for inst in instructions {
-            let mut str_inst = String::from_iter(&inst);
-            if inst.contains(&'=') {
-                let (label, focal_str) = str_inst.split_once('=').unwrap();
-                let focal = focal_str.parse::<u32>().unwrap();
-                let hash_ = Day15::hash(label.chars().collect()) as usize;
-                buckets.get_mut(hash_).unwrap().insert(label, focal);
-            } else if  inst.contains(&'-') {
-                str_inst.remove(str_inst.len() - 1);
-                let hash_ = Day15::hash(str_inst.chars().collect()) as usize;
-                buckets.get_mut(hash_).unwrap().remove(&str_inst);
+        for inst in Day15::steps(data).filter(|s| !s.is_empty()) {
+            if let Some((label, focal_str)) = inst.split_once('=') {
+                let focal = line.number::<u32>(focal_str)?;
+                let hash_ = Day15::hash(label) as usize;
+                buckets[hash_].insert(label, focal);
+            } else if let Some(label) = inst.strip_suffix('-') {
+                let hash_ = Day15::hash(label) as usize;
+                buckets[hash_].remove(label);
+            } else {
+                return Err(line.error_at(inst, "expected `<label>=<focal length>` or `<label>-`"));
             }
-
         }
 
         let total = buckets.iter().enumerate().map(|(i, b)| {
             b.lenses.values().map(|(pos, focal)| (1 + i as u32) * (*pos + 1) * *focal).sum::<u32>()
         }).sum::<u32>();
 
-        println!("Got solution for Day15 Problem2: {}", total);
+        Ok(total.into())
 
-        Ok(())
+    }
 
+    fn generate(rng: &mut Rng, size: usize) -> String {
+        // a small pool of labels so that lenses get replaced and removed
+        let labels: Vec<String> = (0..size.div_ceil(3)).map(|_| rng.string("abcdefghijklmnopqrstuvwxyz", 2..=4)).collect();
+        let steps: Vec<String> = (0..size)
+            .map(|_| {
+                let label = rng.pick(&labels);
+                if rng.chance(0.3) {
+                    format!("{}-", label)
+                } else {
+                    format!("{}={}", label, rng.range(1..=9))
+                }
+            })
+            .collect();
+        steps.join(",") + "\n"
     }
+}
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "\
+rn=1,cm-,qp=3,cm=2,qp-,pc=4,ot=9,ab=5,pc-,pc=6,ot=7
+";
+
+    #[test]
+    fn part1_example() {
+        let input = Input::text("example", EXAMPLE);
+        assert_eq!(Day15::problem1(&input).unwrap(), Answer::Int(1320));
+    }
+
+    #[test]
+    fn part2_example() {
+        let input = Input::text("example", EXAMPLE);
+        assert_eq!(Day15::problem2(&input).unwrap(), Answer::Int(145));
+    }
 }
@@ -1,170 +1,86 @@
+use super::answer::Answer;
 use super::solution::Solution;
 use std::collections::HashSet;
-use std::io::{prelude::*, BufReader, Result};
-use std::fs::File;
-
-const GRID_MAX_X: usize = 109;
-const GRID_MAX_Y: usize = 109;
+use crate::direction::Direction;
+use crate::error::{Error, Result};
+use crate::generate::{self, Rng};
+use crate::input::Input;
+use crate::log::{self, Level};
+use crate::{debug, trace};
+use crate::grid::{Grid, Point};
 
 pub struct Day16;
 
 
 #[derive(Debug, Clone, Copy)]
-enum Direction {
-    North,
-    South,
-    East,
-    West
-}
-
-impl Direction {
-
-    fn back_reflect(self) -> Self {
-        match self {
-            Direction::North => Direction::West,
-            Direction::East => Direction::South,
-            Direction::West => Direction::North,
-            Direction::South => Direction::East,
-        }
-    }
-
-    fn forward_reflect(self) -> Self {
-        match self {
-            Direction::North => Direction::East,
-            Direction::East => Direction::North,
-            Direction::West => Direction::South,
-            Direction::South => Direction::West,
-        }
-    }
-
-}
-
-#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
-struct Point {
-    x: usize,
-    y: usize,
-}
-
-
-fn clamped_add(i: usize, j: usize, clamp: usize) -> usize {
-    if clamp - j < i { clamp } else { i+j }
-}
-
-fn clamped_sub(i: usize, j: usize, clamp: usize) -> usize {
-    if clamp + j > i { clamp } else { i - j }
-}
-
-impl Point {
-
-    fn next(&self, direction: Direction) -> Option<Point> {
-        match direction {
-            Direction::North => {
-                if clamped_sub(self.y, 1, 0) != self.y {
-                    Some(Self { x: self.x, y: self.y - 1 })
-                } else {
-                    None
-                }
-            },
-            Direction::South => {
-                if clamped_add(self.y, 1, GRID_MAX_Y) != self.y {
-                    Some(Self { x: self.x, y: self.y + 1 })
-                } else {
-                    None
-                }
-            },
-            Direction::East => {
-                if clamped_add(self.x, 1, GRID_MAX_X) != self.x {
-                    Some(Self { x: self.x + 1, y: self.y })
-                } else {
-                    None
-                }
-            },
-            Direction::West => {
-                if clamped_sub(self.x, 1, 0) != self.x {
-                    Some(Self { x: self.x - 1 , y: self.y })
-                } else {
-                    None
-                }
-            },
-        }
-    }
-}
-
-
-#[derive(Debug, Clone, Copy)]
-struct Beam {
-    loc: Point,
-    direction: Direction
+pub struct Beam {
+    pub loc: Point,
+    pub direction: Direction
 }
 
 impl Beam {
 
-    fn next(&self) -> Option<Point> {
-        self.loc.next(self.direction)
-    }
-
-    fn interact(self, c: char) -> Vec<Self> {
+    fn interact(self, grid: &Grid<char>) -> Vec<Self> {
         let mut new_beams = vec![];
-        match c {
+        match grid[self.loc] {
             '.' => {
-                // eprintln!("Got '.' continuing");
-                if let Some(loc) = self.loc.next(self.direction) {
+                trace!("Got '.' continuing");
+                if let Some(loc) = grid.step(self.loc, self.direction) {
                     new_beams.push(Self { loc, direction: self.direction })
                 }
             },
             '\\' => {
-                // eprintln!("Got '\\' reflector");
-                if let Some(loc) = self.loc.next(self.direction.back_reflect()) {
-                    new_beams.push(Self { loc, direction: self.direction.back_reflect() });
+                trace!("Got '\\' reflector");
+                if let Some(loc) = grid.step(self.loc, self.direction.reflect_backslash()) {
+                    new_beams.push(Self { loc, direction: self.direction.reflect_backslash() });
                 }
             },
 
             '/' => {
-                // eprintln!("Got '\\' reflector");
-                if let Some(loc) = self.loc.next(self.direction.forward_reflect()) {
-                    new_beams.push(Self { loc, direction: self.direction.forward_reflect() });
+                trace!("Got '/' reflector");
+                if let Some(loc) = grid.step(self.loc, self.direction.reflect_slash()) {
+                    new_beams.push(Self { loc, direction: self.direction.reflect_slash() });
                 }
             }
 
             '-' => {
-                // eprintln!("Got '-' horizontal splitter");
+                trace!("Got '-' horizontal splitter");
                 match &self.direction {
                     Direction::North | Direction::South => {
-                        if let Some(loc) =  self.loc.next(Direction::East) {
+                        if let Some(loc) =  grid.step(self.loc, Direction::East) {
                             new_beams.push(Self { loc, direction: Direction::East });
                         }
-                        if let Some(loc) = self.loc.next(Direction::West) {
+                        if let Some(loc) = grid.step(self.loc, Direction::West) {
                             new_beams.push(Self { loc, direction: Direction::West });
                         }
                     },
                     Direction::East | Direction::West => {
-                        if let Some(loc) = self.loc.next(self.direction) {
+                        if let Some(loc) = grid.step(self.loc, self.direction) {
                             new_beams.push(Self { loc, direction: self.direction});
                         }
                     }
                 }
             },
             '|' => {
-                // eprintln!("Got '|' vertical splitter");
+                trace!("Got '|' vertical splitter");
                 match &self.direction {
                     Direction::East | Direction::West => {
-                        if let Some(loc) = self.loc.next(Direction::North) {
+                        if let Some(loc) = grid.step(self.loc, Direction::North) {
                             new_beams.push(Self { loc , direction: Direction::North });
                         }
-                        if let Some(loc) = self.loc.next(Direction::South) {
+                        if let Some(loc) = grid.step(self.loc, Direction::South) {
                             new_beams.push(Self { loc, direction: Direction::South });
                         }
                     },
                     Direction::North | Direction::South => {
-                        if let Some(loc) = self.loc.next(self.direction) {
+                        if let Some(loc) = grid.step(self.loc, self.direction) {
                             new_beams.push(Self { loc, direction: self.direction});
                         }
                     }
                 }
             },
             a => {
-                // eprintln!("Got invalid char: {}", a);
-                panic!()
+                unreachable!("invalid tile {:?} should have been rejected when parsing", a)
             }
         }
 
@@ -174,34 +90,38 @@ impl Beam {
 }
 
 
-struct Grid {
+pub struct Contraption {
     beams: Vec<Beam>,
-    grid: Vec<Vec<char>>,
-    energized: HashSet<Point>,
+    grid: Grid<char>,
+    pub energized: HashSet<Point>,
+    seen: HashSet<(Point, Direction)>,
 }
 
-impl Grid {
+impl Contraption {
 
-    fn new(grid: Vec<Vec<char>>, beam: Beam) -> Self {
+    pub fn new(grid: Grid<char>, beam: Beam) -> Self {
         let mut energized = HashSet::new();
         energized.insert(beam.loc);
+        let mut seen = HashSet::new();
+        seen.insert((beam.loc, beam.direction));
         Self {
             beams: vec![beam],
             grid,
             energized,
+            seen,
         }
     }
 
-    fn find_candidate_starting_points(&self) -> Vec<Beam> {
+    pub fn find_candidate_starting_points(&self) -> Vec<Beam> {
         let mut candidates = vec![];
-        for (x, direction) in [(0, Direction::East), (self.grid[0].len() - 1, Direction::West)] {
-            for y in 0..self.grid.len() {
+        for (x, direction) in [(0, Direction::East), (self.grid.width() - 1, Direction::West)] {
+            for y in 0..self.grid.height() {
                 let loc = Point { x, y };
                 candidates.push(Beam { loc, direction })
             }
         }
-        for (y, direction) in [(0, Direction::South), (self.grid.len() - 1,Direction::North) ] {
-            for x in 0..self.grid.len() {
+        for (y, direction) in [(0, Direction::South), (self.grid.height() - 1,Direction::North) ] {
+            for x in 0..self.grid.width() {
                 let loc = Point { x, y };
                 candidates.push(Beam { loc, direction })
             }
@@ -209,16 +129,14 @@ impl Grid {
         candidates
     }
 
-    fn get_char_at_point(&self, p: Point) -> char {
-        self.grid[p.y][p.x]
-    }
-
     fn advance(&mut self) {
         let mut new_beams = vec![];
         for beam in &self.beams {
-            new_beams.extend(beam.interact(self.get_char_at_point(beam.loc)))
+            new_beams.extend(beam.interact(&self.grid))
 
         }
+        // a beam that has already passed this way would only retrace its path
+        new_beams.retain(|beam| self.seen.insert((beam.loc, beam.direction)));
         for beam in &new_beams {
             self.energized.insert(beam.loc);
         }
@@ -226,68 +144,111 @@ impl Grid {
         self.beams = new_beams;
     }
 
-    fn run(&mut self) {
-        let total = 700;
-        for i in 0..total {
-            // eprintln!("energized: {:?}", self.energized);
+    pub fn run(&mut self) {
+        while !self.beams.is_empty() {
+            trace!("energized: {:?}", self.energized);
             self.advance();
-            // eprintln!("New beams set to {:?}", self.beams);
+            trace!("New beams set to {:?}", self.beams);
         }
 
-        for i in 0..GRID_MAX_X + 1 {
-            eprintln!();
-            for j in 0..GRID_MAX_Y + 1 {
-                if self.energized.contains(&Point { x: j, y: i }) {
-                    eprint!("#");
-                } else {
-                    eprint!(".");
-                }
+        if log::enabled(Level::Trace) {
+            let mut energized = Grid::filled(self.grid.width(), self.grid.height(), '.');
+            for p in &self.energized {
+                energized[*p] = '#';
             }
+            trace!("energized tiles:\n{}", energized);
         }
-        eprintln!()
     }
 }
 
 
-impl Day16 {
-    fn parse(path: &str) -> Result<Vec<Vec<char>>> {
-        let file = File::open(path)?;
-        Ok(BufReader::new(file).lines().map(|l| {
-            l.unwrap().chars().collect::<Vec<char>>()
-        }).collect())
-    }
-
-}
+impl Solution for Day16 {
+    const DAY: u32 = 16;
+    const TITLE: &'static str = "The Floor Will Be Lava";
 
+    type Parsed = Grid<char>;
 
-impl Solution for Day16 {
+    fn parse(input: &Input) -> Result<Grid<char>> {
+        let grid = Day16::read_input_into_grid(input)?;
+        Day16::validate_grid(input, &grid, ".\\/-|")?;
+        // both parts start a beam at the top left corner, so there has to be one
+        if grid.width() == 0 || grid.height() == 0 {
+            return Err(Error::no_solution(input.name(), "empty grid"));
+        }
+        Ok(grid)
+    }
 
-    fn problem1(path: &str) -> Result<()> {
-        return Ok(());
-        let parsed = Day16::parse(path)?;
-        let mut grid = Grid::new(parsed, Beam { loc: Point { x: 0, y: 0 }, direction: Direction::East});
+    fn part1(_input: &Input, parsed: &Grid<char>) -> Result<Answer> {
+        let mut grid = Contraption::new(parsed.clone(), Beam { loc: Point { x: 0, y: 0 }, direction: Direction::East});
         grid.run();
 
-        println!("Got answer to Day16 problem 1: {}", grid.energized.len());
-        Ok(())
+        Ok(grid.energized.len().into())
     }
 
-    fn problem2(path: &str) -> Result<()> {
-        let parsed = Day16::parse(path)?;
-        let grid = Grid::new(parsed.clone(), Beam { loc: Point { x: 0, y: 0 }, direction: Direction::East });
+    fn part2(input: &Input, parsed: &Grid<char>) -> Result<Answer> {
+        let path = input.name();
+        let grid = Contraption::new(parsed.clone(), Beam { loc: Point { x: 0, y: 0 }, direction: Direction::East });
 
         let mut results = vec![];
         let test_beams = grid.find_candidate_starting_points();
-        eprintln!("Trying {} candidates", test_beams.len());
+        debug!("Trying {} candidates", test_beams.len());
         for beam in test_beams {
-            eprintln!("Trying beam: {:?}", beam);
-            let mut test_grid = Grid::new(parsed.clone(), beam);
+            trace!("Trying beam: {:?}", beam);
+            let mut test_grid = Contraption::new(parsed.clone(), beam);
             test_grid.run();
             results.push(test_grid.energized.len());
         }
 
 
-        println!("Got answer to Day16 problem 1: {}", results.iter().max().unwrap());
-        Ok(())
+        let max = results.iter().max().copied();
+        Ok(max.ok_or_else(|| Error::no_solution(path, "empty grid"))?.into())
+    }
+
+    fn generate(rng: &mut Rng, size: usize) -> String {
+        generate::grid(rng, size, size, |rng| {
+            if rng.chance(0.15) {
+                *rng.pick(&['/', '\\', '-', '|'])
+            } else {
+                '.'
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = r".|...\....
+|.-.\.....
+.....|-...
+........|.
+..........
+.........\
+..../.\\..
+.-.-/..|..
+.|....-|.\
+..//.|....
+";
+
+    #[test]
+    fn part1_example() {
+        let input = Input::text("example", EXAMPLE);
+        assert_eq!(Day16::problem1(&input).unwrap(), Answer::Int(46));
+    }
+
+    #[test]
+    fn part2_example() {
+        let input = Input::text("example", EXAMPLE);
+        assert_eq!(Day16::problem2(&input).unwrap(), Answer::Int(51));
+    }
+
+    #[test]
+    fn empty_grid_is_an_error() {
+        for text in ["", "\n", "  \n  \n"] {
+            let input = Input::text("empty", text);
+            assert!(matches!(Day16::problem1(&input), Err(Error::NoSolution { .. })));
+            assert!(matches!(Day16::problem2(&input), Err(Error::NoSolution { .. })));
+        }
     }
 }
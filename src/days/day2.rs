@@ -1,87 +1,513 @@
+use crate::days::answer::Answer;
 use crate::days::solution::Solution;
+use crate::error::{Error, InputLine, Result};
+use crate::generate::Rng;
+use crate::input::Input;
+use lazy_static::lazy_static;
 use regex::Regex;
-use std::io::Result;
+use std::collections::HashMap;
+use std::fmt;
 
-pub(crate) struct Day2;
+lazy_static! {
+    static ref ID_RE: Regex = Regex::new(r"^\d+$").unwrap();
+    static ref DRAW_RE: Regex = Regex::new(r"^(?P<count>\d+) (?P<color>[a-z]+)$").unwrap();
+}
 
-impl Solution for Day2 {
-    fn problem1(path: &str) -> Result<()> {
-        let lines = Day2::read_input_into_lines(path).unwrap();
-        let game_regex = Regex::new(r"Game (?P<game_id>\d+)").unwrap();
-        let cubes_regex =
-            Regex::new(r"(?P<cube_count>\d+) (?P<cube_color>red|blue|green)").unwrap();
+pub type Color = String;
 
-        let total = lines
-            .iter()
-            .map(|l| {
-                let game_id = game_regex
-                    .captures(l)
-                    .unwrap()
-                    .name("game_id")
-                    .unwrap()
-                    .as_str()
-                    .parse::<u32>()
-                    .unwrap();
-                let not_possible = cubes_regex
-                    .captures_iter(l)
-                    .map(|c| {
-                        let color = c.name("cube_color").unwrap().as_str();
-                        let count: u32 = c
-                            .name("cube_count")
-                            .unwrap()
-                            .as_str()
-                            .parse::<u32>()
-                            .unwrap();
-                        match color {
-                            "red" if count > 12 => true,
-                            "green" if count > 13 => true,
-                            "blue" if count > 14 => true,
-                            _ => false,
-                        }
-                    })
-                    .any(|b| b);
-                (game_id, !not_possible)
-            })
-            .filter(|(_, possible)| *possible)
-            .map(|(g, _)| g)
-            .sum::<u32>();
-
-        println!("Got answer for problem1 part1: {}", total);
-
-        Ok(())
-    }
-
-    fn problem2(path: &str) -> Result<()> {
-        let lines = Day2::read_input_into_lines(path).unwrap();
-        let cubes_regex =
-            Regex::new(r"(?P<cube_count>\d+) (?P<cube_color>red|blue|green)").unwrap();
-
-        let total = lines
+/// A game along with the cubes drawn in each of its rounds
+#[derive(Debug, Clone)]
+pub struct Game {
+    pub id: u32,
+    pub rounds: Vec<HashMap<Color, u32>>,
+}
+
+/// A draw of some cubes of one colour, as slices of the line it's on so that
+/// problems with it can be pointed at
+struct Draw<'a> {
+    count: &'a str,
+    color: &'a str,
+}
+
+/// Split a `Game <id>: <count> <color>, ...; ...` line into its id and the draws of
+/// each round. Rounds are separated by `; ` and draws by `, `, and anything else is
+/// reported at the column it's found.
+fn split_game<'a>(line: &InputLine<'a>) -> Result<(&'a str, Vec<Vec<Draw<'a>>>)> {
+    let Some((id, rounds)) = line
+        .text
+        .strip_prefix("Game ")
+        .and_then(|rest| rest.split_once(':'))
+    else {
+        return Err(line.error("expected `Game <id>: <rounds>`"));
+    };
+    if !ID_RE.is_match(id) {
+        return Err(line.error_at(id, "expected a game id"));
+    }
+
+    let mut draws = vec![];
+    for round in rounds.split(';') {
+        if round.trim().is_empty() {
+            return Err(line.error_at(round, "empty round"));
+        }
+
+        let mut round_draws: Vec<Draw> = vec![];
+        for draw in round.split(',') {
+            let Some(capt) = draw.strip_prefix(' ').and_then(|d| DRAW_RE.captures(d)) else {
+                return Err(line.error_at(draw, "expected a space and then `<count> <color>`"));
+            };
+            let (count, color) = (capt.name("count").unwrap().as_str(), capt.name("color").unwrap().as_str());
+            if round_draws.iter().any(|d| d.color == color) {
+                return Err(line.error_at(color, "colour drawn twice in one round"));
+            }
+            round_draws.push(Draw { count, color });
+        }
+        draws.push(round_draws);
+    }
+
+    Ok((id, draws))
+}
+
+impl Game {
+    fn parse(line: &InputLine) -> Result<Self> {
+        let (id, draws) = split_game(line)?;
+
+        let mut rounds = vec![];
+        for round in draws {
+            let mut cubes = HashMap::new();
+            for draw in round {
+                cubes.insert(draw.color.to_owned(), line.number(draw.count)?);
+            }
+            rounds.push(cubes);
+        }
+
+        Ok(Self {
+            id: line.number(id)?,
+            rounds,
+        })
+    }
+
+    /// Whether every round could have been drawn from `bag`
+    pub fn is_possible(&self, bag: &Bag) -> bool {
+        self.rounds
             .iter()
-            .map(|l| {
-                let cube_samples = cubes_regex.captures_iter(l);
-                let minimums: &mut [u32] = &mut [0, 0, 0];
-                for sample in cube_samples {
-                    let color = sample.name("cube_color").unwrap().as_str();
-                    let count: u32 = sample
-                        .name("cube_count")
-                        .unwrap()
-                        .as_str()
-                        .parse::<u32>()
-                        .unwrap();
-                    match color {
-                        "red" if count > minimums[0] => minimums[0] = count,
-                        "green" if count > minimums[1] => minimums[1] = count,
-                        "blue" if count > minimums[2] => minimums[2] = count,
-                        _ => {}
-                    }
+            .flatten()
+            .all(|(color, count)| *count <= bag.count(color))
+    }
+
+    /// The smallest bag that every round could have been drawn from, with the colours
+    /// in alphabetical order
+    pub fn minimum_bag(&self) -> Bag {
+        let mut bag = Bag::default();
+        for round in &self.rounds {
+            for (color, count) in round {
+                if *count > bag.count(color) {
+                    bag.set(color, *count);
                 }
-                minimums.iter().copied().reduce(|acc, n| n * acc).unwrap()
-            })
-            .sum::<u32>();
+            }
+        }
+        bag.cubes.sort();
+        bag
+    }
+
+    /// The product of the fewest cubes of each of the colours in `bag` that the game
+    /// could be played with, so a colour that's never drawn makes it 0, or None if
+    /// it doesn't fit in a u64
+    pub fn power(&self, bag: &Bag) -> Option<u64> {
+        let minimum = self.minimum_bag();
+        bag.cubes
+            .iter()
+            .try_fold(1u64, |power, (color, _)| power.checked_mul(minimum.count(color).into()))
+    }
+}
+
+/// The cubes in the bag, in the order they were given
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Bag {
+    pub cubes: Vec<(Color, u32)>,
+}
+
+impl Bag {
+    /// The bag from the puzzle: 12 red, 13 green and 14 blue cubes
+    pub fn standard() -> Self {
+        let mut bag = Bag::default();
+        bag.set("red", 12);
+        bag.set("green", 13);
+        bag.set("blue", 14);
+        bag
+    }
+
+    /// A bag written like a round, as in `12 red, 13 green, 14 blue`, over any number
+    /// of lines
+    pub fn parse(input: &Input) -> Result<Self> {
+        let path = input.name();
+        let mut bag = Bag::default();
+        for (i, text) in input.lines()?.enumerate() {
+            let text = text?;
+            let line = InputLine::new(path, i, &text);
+            for part in line.text.split(',').filter(|part| !part.trim().is_empty()) {
+                let Some(draw) = DRAW_RE.captures(part.trim()) else {
+                    return Err(line.error_at(part, "expected `<count> <color>`"));
+                };
+                bag.set(&draw["color"], line.number(&draw["count"])?);
+            }
+        }
+        Ok(bag)
+    }
+
+    /// How many cubes of `color` there are, none for a colour that isn't in the bag
+    pub fn count(&self, color: &str) -> u32 {
+        self.cubes
+            .iter()
+            .find(|(c, _)| c == color)
+            .map_or(0, |(_, count)| *count)
+    }
+
+    pub fn set(&mut self, color: &str, count: u32) {
+        match self.cubes.iter_mut().find(|(c, _)| c == color) {
+            Some((_, c)) => *c = count,
+            None => self.cubes.push((color.to_owned(), count)),
+        }
+    }
+}
+
+impl fmt::Display for Bag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let cubes: Vec<String> = self
+            .cubes
+            .iter()
+            .map(|(color, count)| format!("{} {}", count, color))
+            .collect();
+        write!(f, "{}", cubes.join(", "))
+    }
+}
+
+/// Print whether each game is possible with `bag`, the fewest cubes it could be
+/// played with and its power, followed by the totals of both parts
+pub fn print_games(games: &[Game], bag: &Bag) {
+    println!("Bag: {}", bag);
+    for game in games {
+        let possible = if game.is_possible(bag) { "possible" } else { "impossible" };
+        println!(
+            "Game {}: {:<10}  fewest {}  power {}",
+            game.id,
+            possible,
+            game.minimum_bag(),
+            show_total(game.power(bag))
+        );
+    }
+
+    let possible = games.iter().filter(|game| game.is_possible(bag)).count();
+    println!(
+        "{} of {} games possible, ids summing to {}",
+        possible,
+        games.len(),
+        show_total(Day2::possible_ids(games, bag))
+    );
+    println!("Total power: {}", show_total(Day2::total_power(games, bag)));
+}
+
+fn show_total(total: Option<u64>) -> String {
+    total.map_or_else(|| "overflow".to_owned(), |t| t.to_string())
+}
+
+/// Every problem with the games in `input` rather than just the first: lines that
+/// aren't well formed, ids that don't count up from 1 and colours that aren't in
+/// `bag`
+pub fn validate(input: &Input, bag: &Bag) -> Result<Vec<Error>> {
+    let path = input.name();
+    let mut problems = vec![];
+
+    for (i, text) in input.lines()?.enumerate() {
+        let text = text?;
+        let line = InputLine::new(path, i, &text);
+        let (id, draws) = match split_game(&line) {
+            Ok(game) => game,
+            Err(e) => {
+                problems.push(e);
+                continue;
+            }
+        };
+
+        match line.number::<u32>(id) {
+            Ok(n) if n as usize != line.number => {
+                problems.push(line.error_at(id, format!("expected game {}", line.number)));
+            }
+            Ok(_) => {}
+            Err(e) => problems.push(e),
+        }
+        for draw in draws.iter().flatten() {
+            if let Err(e) = line.number::<u32>(draw.count) {
+                problems.push(e);
+            }
+            if !bag.cubes.iter().any(|(color, _)| color == draw.color) {
+                problems.push(line.error_at(draw.color, format!("no {} cubes in the bag", draw.color)));
+            }
+        }
+    }
+
+    Ok(problems)
+}
+
+/// How often a colour is drawn and how many cubes of it at a time, along with how
+/// many games never draw more of it than the bag holds
+#[derive(Debug, Clone)]
+pub struct ColorStats {
+    pub color: Color,
+    pub draws: usize,
+    pub min: u32,
+    pub max: u32,
+    pub mean: f64,
+    pub within: usize,
+}
 
-        println!("Got answer for problem2 part2: {}", total);
+/// The stats of every colour in `bag` in its order, followed by any other colours
+/// that are drawn in alphabetical order
+pub fn color_stats(games: &[Game], bag: &Bag) -> Vec<ColorStats> {
+    let mut colors: Vec<&str> = bag.cubes.iter().map(|(color, _)| color.as_str()).collect();
+    let mut others: Vec<&str> = games
+        .iter()
+        .flat_map(|game| game.rounds.iter().flat_map(|round| round.keys()))
+        .map(String::as_str)
+        .filter(|color| !colors.contains(color))
+        .collect();
+    others.sort();
+    others.dedup();
+    colors.extend(others);
+
+    colors
+        .into_iter()
+        .map(|color| {
+            let counts: Vec<u32> = games
+                .iter()
+                .flat_map(|game| game.rounds.iter().filter_map(|round| round.get(color)))
+                .copied()
+                .collect();
+            ColorStats {
+                color: color.to_owned(),
+                draws: counts.len(),
+                min: counts.iter().min().copied().unwrap_or(0),
+                max: counts.iter().max().copied().unwrap_or(0),
+                mean: counts.iter().map(|&count| u64::from(count)).sum::<u64>() as f64
+                    / counts.len().max(1) as f64,
+                within: games
+                    .iter()
+                    .filter(|game| game.minimum_bag().count(color) <= bag.count(color))
+                    .count(),
+            }
+        })
+        .collect()
+}
+
+pub fn print_stats(games: &[Game], bag: &Bag) {
+    println!("Bag: {}", bag);
+    for stats in color_stats(games, bag) {
+        println!(
+            "{}: {} draws, min {}, max {}, mean {:.2}; {} of {} games draw at most {}",
+            stats.color,
+            stats.draws,
+            stats.min,
+            stats.max,
+            stats.mean,
+            stats.within,
+            games.len(),
+            bag.count(&stats.color),
+        );
+    }
+}
+
+pub struct Day2;
+
+impl Day2 {
+    /// The sum of the ids of the games that are possible with `bag`, or None if it
+    /// doesn't fit in a u64
+    pub fn possible_ids(games: &[Game], bag: &Bag) -> Option<u64> {
+        games
+            .iter()
+            .filter(|game| game.is_possible(bag))
+            .try_fold(0u64, |total, game| total.checked_add(game.id.into()))
+    }
+
+    /// The sum of the powers of every game over the colours in `bag`, or None if it
+    /// doesn't fit in a u64
+    pub fn total_power(games: &[Game], bag: &Bag) -> Option<u64> {
+        games
+            .iter()
+            .try_fold(0u64, |total, game| total.checked_add(game.power(bag)?))
+    }
+}
+
+impl Solution for Day2 {
+    const DAY: u32 = 2;
+    const TITLE: &'static str = "Cube Conundrum";
+
+    type Parsed = Vec<Game>;
+
+    fn parse(input: &Input) -> Result<Vec<Game>> {
+        let path = input.name();
+        let mut games = vec![];
+        for (i, text) in Day2::input_lines(input)?.enumerate() {
+            let text = text?;
+            games.push(Game::parse(&InputLine::new(path, i, &text))?);
+        }
+        Ok(games)
+    }
+
+    fn part1(input: &Input, games: &Vec<Game>) -> Result<Answer> {
+        Day2::possible_ids(games, &Bag::standard())
+            .map(Answer::from)
+            .ok_or_else(|| Error::no_solution(input.name(), "the ids add up to too much to count"))
+    }
+
+    fn part2(input: &Input, games: &Vec<Game>) -> Result<Answer> {
+        Day2::total_power(games, &Bag::standard())
+            .map(Answer::from)
+            .ok_or_else(|| Error::no_solution(input.name(), "too much power to count"))
+    }
+
+    fn generate(rng: &mut Rng, size: usize) -> String {
+        let mut out = String::new();
+        for id in 1..=size {
+            let mut rounds = vec![];
+            for _ in 0..rng.usize(1..=5) {
+                let mut colors = ["red", "green", "blue"];
+                rng.shuffle(&mut colors);
+                let draws: Vec<String> = colors[..rng.usize(1..=3)]
+                    .iter()
+                    .map(|color| format!("{} {}", rng.range(1..=20), color))
+                    .collect();
+                rounds.push(draws.join(", "));
+            }
+            out += &format!("Game {}: {}\n", id, rounds.join("; "));
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "\
+Game 1: 3 blue, 4 red; 1 red, 2 green, 6 blue; 2 green
+Game 2: 1 blue, 2 green; 3 green, 4 blue, 1 red; 1 green, 1 blue
+Game 3: 8 green, 6 blue, 20 red; 5 blue, 4 red, 13 green; 5 green, 1 red
+Game 4: 1 green, 3 red, 6 blue; 3 green, 6 red; 3 green, 15 blue, 14 red
+Game 5: 6 red, 1 blue, 3 green; 2 blue, 1 red, 2 green
+";
+
+    #[test]
+    fn part1_example() {
+        let input = Input::text("example", EXAMPLE);
+        assert_eq!(Day2::problem1(&input).unwrap(), Answer::Int(8));
+    }
+
+    #[test]
+    fn part2_example() {
+        let input = Input::text("example", EXAMPLE);
+        assert_eq!(Day2::problem2(&input).unwrap(), Answer::Int(2286));
+    }
+
+    #[test]
+    fn powers_past_u32() {
+        let input = Input::text("huge", "Game 1: 3000 red, 3000 green, 3000 blue\n");
+        assert_eq!(Day2::problem2(&input).unwrap(), Answer::Int(27_000_000_000));
+    }
+
+    #[test]
+    fn powers_past_u64_are_an_error() {
+        let game = "4000000000 red, 4000000000 green, 4000000000 blue";
+        let input = Input::text("huge", &format!("Game 1: {}\nGame 2: {}\n", game, game));
+        let games = Day2::parse(&input).unwrap();
+        assert_eq!(games[0].power(&Bag::standard()), None);
+        assert_eq!(Day2::total_power(&games, &Bag::standard()), None);
+        assert!(matches!(Day2::part2(&input, &games), Err(Error::NoSolution { .. })));
+    }
+
+    /// The line, column and message of the error in `err`
+    fn location(err: &Error) -> (usize, usize, &str) {
+        match err {
+            Error::Parse {
+                line, column, message, ..
+            } => (*line, *column, message),
+            _ => panic!("expected a parse error, got {}", err),
+        }
+    }
+
+    fn parse_error(text: &str) -> (usize, usize, String) {
+        let err = Day2::parse(&Input::text("bad", text)).unwrap_err();
+        let (line, column, message) = location(&err);
+        (line, column, message.to_owned())
+    }
+
+    #[test]
+    fn missing_space_before_a_draw() {
+        let (line, column, message) = parse_error("Game 1: 1 red\nGame 2:3 blue\n");
+        assert_eq!((line, column), (2, 8));
+        assert_eq!(message, "expected a space and then `<count> <color>`");
+    }
+
+    #[test]
+    fn empty_round() {
+        let (line, column, message) = parse_error("Game 1: 3 blue;; 2 red\n");
+        assert_eq!((line, column), (1, 16));
+        assert_eq!(message, "empty round");
+    }
+
+    #[test]
+    fn colour_drawn_twice() {
+        let (line, column, message) = parse_error("Game 1: 3 blue, 4 blue\n");
+        assert_eq!((line, column), (1, 19));
+        assert_eq!(message, "colour drawn twice in one round");
+    }
+
+    #[test]
+    fn validate_reports_every_problem() {
+        let input = Input::text("bad", "Game 1: 2 purple\nGame 3: 1 red\nGame 3: 1 red, 1 red\n");
+        let problems = validate(&input, &Bag::standard()).unwrap();
+        let locations: Vec<_> = problems.iter().map(location).collect();
+        assert_eq!(
+            locations,
+            [
+                (1, 11, "no purple cubes in the bag"),
+                (2, 6, "expected game 2"),
+                (3, 18, "colour drawn twice in one round"),
+            ]
+        );
+    }
+
+    #[test]
+    fn validate_accepts_the_example() {
+        let input = Input::text("example", EXAMPLE);
+        assert!(validate(&input, &Bag::standard()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn bag_over_several_lines() {
+        let bag = Bag::parse(&Input::text("--bag", "12 red, 13 green\n14 blue\n")).unwrap();
+        assert_eq!(bag, Bag::standard());
+
+        let err = Bag::parse(&Input::text("--bag", "12 red, green\n")).unwrap_err();
+        assert_eq!(location(&err), (1, 8, "expected `<count> <color>`"));
+    }
+
+    #[test]
+    fn stats_of_the_example() {
+        let games = Day2::parse(&Input::text("example", EXAMPLE)).unwrap();
+        let stats = color_stats(&games, &Bag::standard());
+        let colors: Vec<&str> = stats.iter().map(|s| s.color.as_str()).collect();
+        assert_eq!(colors, ["red", "green", "blue"]);
+
+        let red = &stats[0];
+        assert_eq!((red.draws, red.min, red.max, red.within), (11, 1, 20, 3));
+        assert_eq!(red.mean, 61.0 / 11.0);
+    }
 
-        Ok(())
+    #[test]
+    fn stats_of_huge_draws() {
+        let input = Input::text("huge", "Game 1: 4000000000 red; 4000000000 red; 2 pink\n");
+        let games = Day2::parse(&input).unwrap();
+        let stats = color_stats(&games, &Bag::standard());
+        assert_eq!(stats[0].mean, 4_000_000_000.0);
+        assert_eq!((stats[3].color.as_str(), stats[3].draws, stats[3].within), ("pink", 1, 0));
     }
 }
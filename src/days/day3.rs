@@ -1,116 +1,518 @@
+use crate::days::answer::Answer;
 use crate::days::solution::Solution;
-use lazy_static::lazy_static;
-use regex::Regex;
-use std::io::Result;
+use crate::error::{Error, InputLine, Result};
+use crate::generate::Rng;
+use crate::input::Input;
+use crate::grid::{Grid, Point};
+use crate::warn;
+use std::collections::BTreeSet;
+use std::str::FromStr;
 
-pub(crate) struct Day3;
+/// A number in the schematic, starting at `start` and `len` digits long, with the
+/// indices of the symbols next to it
+#[derive(Debug, Clone)]
+pub struct Number {
+    pub value: u32,
+    pub start: Point,
+    pub len: usize,
+    pub symbols: Vec<usize>,
+}
 
-lazy_static! {
-    static ref SYMBOL_RE: Regex = Regex::new(r"[^\.\w\d]").unwrap();
-    static ref NUMBER_RE: Regex = Regex::new(r"\d+").unwrap();
+/// A symbol in the schematic with the indices of the numbers next to it
+#[derive(Debug, Clone)]
+pub struct Symbol {
+    pub c: char,
+    pub pos: Point,
+    pub numbers: Vec<usize>,
 }
 
-impl Day3 {
-    fn clamped_sub(num: usize, sub: usize, clamp: usize) -> usize {
-        if (clamp + sub) > num {
-            clamp
-        } else {
-            num - sub
+/// The numbers and symbols of the schematic and which of them are next to each
+/// other, both in reading order
+#[derive(Debug, Clone, Default)]
+pub struct Schematic {
+    pub numbers: Vec<Number>,
+    pub symbols: Vec<Symbol>,
+}
+
+impl Schematic {
+    /// Read every number and symbol off the grid in one pass and then link each
+    /// symbol to the numbers around it
+    pub fn new(path: &str, grid: &Grid<char>) -> Result<Self> {
+        let mut schematic = Schematic::default();
+        // which number covers each tile, if any
+        let mut owners: Grid<Option<usize>> = Grid::filled(grid.width(), grid.height(), None);
+
+        for (y, row) in grid.rows().enumerate() {
+            let text = String::from_iter(row);
+            let line = InputLine::new(path, y, &text);
+            let mut x = 0;
+            while x < row.len() {
+                if row[x].is_ascii_digit() {
+                    let len = row[x..].iter().take_while(|c| c.is_ascii_digit()).count();
+                    // the digits are ascii, but the tiles before them might not be
+                    let offset = text.char_indices().nth(x).map_or(text.len(), |(i, _)| i);
+                    let value = line.number(&text[offset..offset + len])?;
+                    for i in x..x + len {
+                        owners[Point::new(i, y)] = Some(schematic.numbers.len());
+                    }
+                    schematic.numbers.push(Number {
+                        value,
+                        start: Point::new(x, y),
+                        len,
+                        symbols: vec![],
+                    });
+                    x += len;
+                } else {
+                    if Day3::is_symbol(row[x]) {
+                        schematic.symbols.push(Symbol {
+                            c: row[x],
+                            pos: Point::new(x, y),
+                            numbers: vec![],
+                        });
+                    }
+                    x += 1;
+                }
+            }
+        }
+
+        for (i, symbol) in schematic.symbols.iter_mut().enumerate() {
+            let numbers: BTreeSet<usize> = grid.neighbors8(symbol.pos).filter_map(|p| owners[p]).collect();
+            for &n in &numbers {
+                schematic.numbers[n].symbols.push(i);
+            }
+            symbol.numbers = numbers.into_iter().collect();
+        }
+
+        Ok(schematic)
+    }
+
+    /// The numbers next to at least one symbol
+    pub fn part_numbers(&self) -> impl Iterator<Item = &Number> {
+        self.numbers.iter().filter(|n| !n.symbols.is_empty())
+    }
+
+    /// The `*` symbols next to exactly `neighbours` numbers
+    pub fn gears(&self, neighbours: usize) -> impl Iterator<Item = &Symbol> {
+        self.symbols
+            .iter()
+            .filter(move |s| s.c == '*' && s.numbers.len() == neighbours)
+    }
+
+    /// The numbers next to at least one `c` symbol
+    pub fn adjacent_to(&self, c: char) -> impl Iterator<Item = &Number> {
+        self.numbers
+            .iter()
+            .filter(move |n| n.symbols.iter().any(|&s| self.symbols[s].c == c))
+    }
+
+    /// Every gear under `rules` along with its ratio. Symbols next to more numbers
+    /// than a gear can be are set aside rather than treated as an error.
+    pub fn gear_ratios(&self, rules: &GearRules) -> Gears {
+        let mut gears = Gears::default();
+        for (i, symbol) in self.symbols.iter().enumerate() {
+            if !rules.symbols.contains(&symbol.c) {
+                continue;
+            }
+            let n = symbol.numbers.len();
+            if rules.neighbours.allows(n) {
+                let ratio = rules
+                    .aggregate
+                    .apply(self.neighbours(symbol).map(|n| n.value as u64));
+                gears.gears.push((i, ratio));
+            } else if rules.neighbours.is_overloaded(n) {
+                gears.overloaded.push(i);
+            }
         }
+        gears
     }
 
-    fn clamped_add(num: usize, add: usize, clamp: usize) -> usize {
-        if (clamp - add) < num {
-            clamp
+    /// The numbers next to `symbol`
+    pub fn neighbours<'a>(&'a self, symbol: &'a Symbol) -> impl Iterator<Item = &'a Number> {
+        symbol.numbers.iter().map(|&n| &self.numbers[n])
+    }
+}
+
+/// How many numbers a symbol has to be next to for it to be a gear
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Neighbours {
+    Exactly(usize),
+    AtLeast(usize),
+    /// Inclusive on both ends
+    Between(usize, usize),
+}
+
+impl Neighbours {
+    pub fn allows(self, n: usize) -> bool {
+        match self {
+            Neighbours::Exactly(exact) => n == exact,
+            Neighbours::AtLeast(min) => n >= min,
+            Neighbours::Between(min, max) => (min..=max).contains(&n),
+        }
+    }
+
+    /// Whether `n` numbers are more than any gear can be next to
+    pub fn is_overloaded(self, n: usize) -> bool {
+        match self {
+            Neighbours::Exactly(max) | Neighbours::Between(_, max) => n > max,
+            Neighbours::AtLeast(_) => false,
+        }
+    }
+}
+
+/// Written as `2`, `2..` or a range like `2..=4` or `2..5`. Ranges that can't hold
+/// any number are rejected.
+impl FromStr for Neighbours {
+    type Err = ();
+
+    fn from_str(s: &str) -> std::result::Result<Self, ()> {
+        let number = |n: &str| n.parse::<usize>().map_err(|_| ());
+        let between = |min: usize, max: usize| (min <= max).then_some(Neighbours::Between(min, max)).ok_or(());
+        if let Some((min, max)) = s.split_once("..=") {
+            between(number(min)?, number(max)?)
+        } else if let Some(min) = s.strip_suffix("..") {
+            Ok(Neighbours::AtLeast(number(min)?))
+        } else if let Some((min, max)) = s.split_once("..") {
+            between(number(min)?, number(max)?.checked_sub(1).ok_or(())?)
         } else {
-            num + add
+            Ok(Neighbours::Exactly(number(s)?))
         }
     }
+}
 
-    fn check_surrounding(i: usize, j: usize, k: usize, grid: &[Vec<char>]) -> bool {
-        let line_len = grid[i].len() - 1;
-        let i_min = Day3::clamped_sub(i, 1, 0);
-        let i_max = Day3::clamped_add(i, 2, grid.len());
-        let min = Day3::clamped_sub(j, 1, 0);
-        let max = Day3::clamped_add(k, 1, line_len);
+/// How the numbers next to a gear are combined into its ratio
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Aggregate {
+    Product,
+    Sum,
+    Max,
+}
 
-        for line in &grid[i_min..i_max] {
-            let to_check = String::from_iter(&line[min..max]);
-            if SYMBOL_RE.is_match(&to_check) {
-                return true;
-            }
+impl Aggregate {
+    /// The values combined, or None if that doesn't fit in a u64
+    pub fn apply(self, mut values: impl Iterator<Item = u64>) -> Option<u64> {
+        match self {
+            Aggregate::Product => values.try_fold(1u64, u64::checked_mul),
+            Aggregate::Sum => values.try_fold(0u64, u64::checked_add),
+            Aggregate::Max => Some(values.max().unwrap_or(0)),
         }
+    }
+}
 
-        false
+impl FromStr for Aggregate {
+    type Err = ();
+
+    fn from_str(s: &str) -> std::result::Result<Self, ()> {
+        match s {
+            "product" => Ok(Aggregate::Product),
+            "sum" => Ok(Aggregate::Sum),
+            "max" => Ok(Aggregate::Max),
+            _ => Err(()),
+        }
     }
+}
 
-    fn extract_gear_ratio(i: usize, j: usize, grid: &[Vec<char>]) -> Option<u32> {
-        let i_min = Day3::clamped_sub(i, 1, 0);
-        let i_max = Day3::clamped_add(i, 2, grid.len());
-        let j_min = Day3::clamped_sub(j, 1, 0);
-        let j_max = Day3::clamped_add(j, 2, grid[0].len());
+/// Which symbols are gears and how their ratios are worked out
+#[derive(Debug, Clone, PartialEq)]
+pub struct GearRules {
+    pub symbols: Vec<char>,
+    pub neighbours: Neighbours,
+    pub aggregate: Aggregate,
+}
+
+/// The puzzle's rules: a `*` next to exactly two numbers, multiplied together
+impl Default for GearRules {
+    fn default() -> Self {
+        Self {
+            symbols: vec!['*'],
+            neighbours: Neighbours::Exactly(2),
+            aggregate: Aggregate::Product,
+        }
+    }
+}
 
-        let mut nums = vec![];
-        for line in &grid[i_min..i_max] {
-            let s = String::from_iter(line);
-            let num_captures = NUMBER_RE.captures_iter(&s);
+/// The gears found under some rules, as symbol indices along with their ratios, and
+/// the gear symbols that were next to too many numbers to be one. A ratio is None
+/// when it doesn't fit in a u64.
+#[derive(Debug, Clone, Default)]
+pub struct Gears {
+    pub gears: Vec<(usize, Option<u64>)>,
+    pub overloaded: Vec<usize>,
+}
 
-            for num_capture in num_captures {
-                if let Some(num_match) = num_capture.get(0) {
-                    let start = num_match.start();
-                    let end = num_match.end() - 1;
-                    if (j_min..j_max).contains(&start) || (j_min..j_max).contains(&end) {
-                        let num = num_match.as_str().parse::<u32>().unwrap();
-                        nums.push(num)
-                    }
-                }
+impl Gears {
+    /// The sum of the ratios, or None if it or any ratio doesn't fit in a u64
+    pub fn total(&self) -> Option<u64> {
+        self.gears
+            .iter()
+            .try_fold(0u64, |total, &(_, ratio)| total.checked_add(ratio?))
+    }
+}
+
+/// A question that can be asked of a schematic from the command line
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Query {
+    PartNumbers,
+    Gears(usize),
+    Adjacent(char),
+}
+
+/// Print the answer to `query` with a line per number or symbol, each at its
+/// 1-based `line:column`
+pub fn print_query(schematic: &Schematic, query: Query) {
+    let print_numbers = |numbers: Vec<&Number>, what: &str| {
+        for n in &numbers {
+            println!("{} {}", location(n.start), n.value);
+        }
+        let total: u64 = numbers.iter().map(|n| n.value as u64).sum();
+        println!("{} {} summing to {}", numbers.len(), what, total);
+    };
+
+    match query {
+        Query::PartNumbers => print_numbers(schematic.part_numbers().collect(), "part numbers"),
+        Query::Adjacent(c) => print_numbers(schematic.adjacent_to(c).collect(), &format!("numbers next to `{}`", c)),
+        Query::Gears(neighbours) => {
+            let gears: Vec<&Symbol> = schematic.gears(neighbours).collect();
+            for gear in &gears {
+                let numbers: Vec<String> = schematic.neighbours(gear).map(|n| n.value.to_string()).collect();
+                println!("{} {} {}", location(gear.pos), gear.c, numbers.join(", "));
             }
+            println!("{} gears next to {} numbers", gears.len(), neighbours);
         }
+    }
+}
+
+/// 1-based `line:column` of a point
+fn location(p: Point) -> String {
+    format!("{}:{}", p.y + 1, p.x + 1)
+}
+
+/// Print every gear under `rules` with its numbers and ratio, then every overloaded
+/// symbol, then the total
+pub fn print_gears(schematic: &Schematic, rules: &GearRules) {
+    let gears = schematic.gear_ratios(rules);
+    for &(i, ratio) in &gears.gears {
+        let gear = &schematic.symbols[i];
+        let numbers: Vec<String> = schematic.neighbours(gear).map(|n| n.value.to_string()).collect();
+        println!("{} {} {} -> {}", location(gear.pos), gear.c, numbers.join(", "), show_ratio(ratio));
+    }
+    for &i in &gears.overloaded {
+        let symbol = &schematic.symbols[i];
+        println!(
+            "{} {} is next to {} numbers, too many for a gear",
+            location(symbol.pos),
+            symbol.c,
+            symbol.numbers.len()
+        );
+    }
+    println!("{} gears, ratios summing to {}", gears.gears.len(), show_ratio(gears.total()));
+}
+
+fn show_ratio(ratio: Option<u64>) -> String {
+    ratio.map_or_else(|| "overflow".to_owned(), |r| r.to_string())
+}
 
-        match nums.len() {
-            n if n < 2 => None,
-            n if n > 2 => panic!("Found more than 2 adjacent numbers in gear ratio"),
-            _ => Some(nums.into_iter().reduce(|acc, n| acc * n).unwrap()),
+pub struct Day3;
+
+impl Day3 {
+    fn is_symbol(c: char) -> bool {
+        c != '.' && c != '_' && !c.is_alphanumeric()
+    }
+
+    /// The point where the number covering `p` starts
+    fn number_start(mut p: Point, grid: &Grid<char>) -> Point {
+        while let Some(left) = grid.offset(p, -1, 0) {
+            if !grid[left].is_ascii_digit() {
+                break;
+            }
+            p = left;
         }
+        p
     }
 }
 
 impl Solution for Day3 {
-    fn problem1(path: &str) -> Result<()> {
-        let grid = Day3::read_input_into_grid(path)?;
-
-        let mut total: u32 = 0;
-        for (i, line) in grid.iter().enumerate() {
-            let line_str = String::from_iter(line);
-            for capture in NUMBER_RE.captures_iter(&line_str) {
-                let num_match = capture.get(0).unwrap();
-                let include = Day3::check_surrounding(i, num_match.start(), num_match.end(), &grid);
-                if include {
-                    total += num_match.as_str().parse::<u32>().unwrap();
+    const DAY: u32 = 3;
+    const TITLE: &'static str = "Gear Ratios";
+
+    type Parsed = Schematic;
+
+    fn parse(input: &Input) -> Result<Schematic> {
+        Schematic::new(input.name(), &Day3::read_input_into_grid(input)?)
+    }
+
+    fn part1(_input: &Input, schematic: &Schematic) -> Result<Answer> {
+        let total: u64 = schematic.part_numbers().map(|n| n.value as u64).sum();
+        Ok(total.into())
+    }
+
+    fn part2(input: &Input, schematic: &Schematic) -> Result<Answer> {
+        let gears = schematic.gear_ratios(&GearRules::default());
+        for &i in &gears.overloaded {
+            let symbol = &schematic.symbols[i];
+            warn!(
+                "{}:{}: `{}` is next to {} numbers, too many for a gear, so it's left out",
+                input.name(),
+                location(symbol.pos),
+                symbol.c,
+                symbol.numbers.len()
+            );
+        }
+        gears
+            .total()
+            .map(Answer::from)
+            .ok_or_else(|| Error::no_solution(input.name(), "the gear ratios add up to too much to count"))
+    }
+
+    fn generate(rng: &mut Rng, size: usize) -> String {
+        let side = size.max(3);
+        let mut grid = Grid::filled(side, side, '.');
+        for y in 0..side {
+            let mut x = 0;
+            while x < side {
+                if rng.chance(0.2) {
+                    let digits = rng.range(1..=999).to_string();
+                    for (i, c) in digits.chars().enumerate().take_while(|(i, _)| x + i < side) {
+                        grid[Point::new(x + i, y)] = c;
+                    }
+                    // leave a gap so neighbouring numbers don't run together
+                    x += digits.len() + 1;
+                } else {
+                    if rng.chance(0.08) {
+                        grid[Point::new(x, y)] = *rng.pick(&['*', '*', '#', '$', '+', '/', '=', '@', '%', '&', '-']);
+                    }
+                    x += 1;
                 }
             }
         }
 
-        println!("Got solution to Day3 Problem1: {}", total);
-        Ok(())
+        // keep gears next to at most two numbers so that part 2 has nothing to warn
+        // about
+        let gears: Vec<Point> = grid.iter().filter(|(_, c)| **c == '*').map(|(p, _)| p).collect();
+        for gear in gears {
+            let numbers = grid
+                .neighbors8(gear)
+                .filter(|p| grid[*p].is_ascii_digit())
+                .map(|p| Day3::number_start(p, &grid))
+                .collect::<BTreeSet<_>>();
+            if numbers.len() > 2 {
+                grid[gear] = '#';
+            }
+        }
+
+        grid.to_string()
     }
+}
 
-    fn problem2(path: &str) -> Result<()> {
-        let grid = Day3::read_input_into_grid(path)?;
-        let total = grid
-            .iter()
-            .enumerate()
-            .map(|(i, line)| {
-                line.iter()
-                    .enumerate()
-                    .filter(|(_, c)| **c == '*')
-                    .map(|(j, _)| Day3::extract_gear_ratio(i, j, &grid))
-                    .map(|o| o.unwrap_or(0))
-                    .sum::<u32>()
-            })
-            .sum::<u32>();
-
-        println!("Got solution to Day3 Problem2: {}", total);
-        Ok(())
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "\
+467..114..
+...*......
+..35..633.
+......#...
+617*......
+.....+.58.
+..592.....
+......755.
+...$.*....
+.664.598..
+";
+
+    #[test]
+    fn part1_example() {
+        let input = Input::text("example", EXAMPLE);
+        assert_eq!(Day3::problem1(&input).unwrap(), Answer::Int(4361));
+    }
+
+    #[test]
+    fn part2_example() {
+        let input = Input::text("example", EXAMPLE);
+        assert_eq!(Day3::problem2(&input).unwrap(), Answer::Int(467835));
+    }
+
+    #[test]
+    fn part_numbers_summing_past_u32() {
+        let input = Input::text("huge", "4000000000\n.....+....\n4000000000\n");
+        assert_eq!(Day3::problem1(&input).unwrap(), Answer::Int(8_000_000_000));
+    }
+
+    #[test]
+    fn neighbours_from_str() {
+        assert_eq!("2".parse(), Ok(Neighbours::Exactly(2)));
+        assert_eq!("2..".parse(), Ok(Neighbours::AtLeast(2)));
+        assert_eq!("2..=4".parse(), Ok(Neighbours::Between(2, 4)));
+        assert_eq!("2..5".parse(), Ok(Neighbours::Between(2, 4)));
+        assert_eq!("2..=2".parse(), Ok(Neighbours::Between(2, 2)));
+    }
+
+    #[test]
+    fn empty_neighbour_ranges_are_rejected() {
+        for s in ["5..=2", "2..2", "5..2", "2..0", "0..0", "", "x", "..2", "2..=x"] {
+            assert_eq!(s.parse::<Neighbours>(), Err(()), "{}", s);
+        }
+    }
+
+    #[test]
+    fn neighbours_allow_and_overload() {
+        let between = Neighbours::Between(2, 3);
+        assert!(!between.allows(1) && between.allows(2) && between.allows(3) && !between.allows(4));
+        assert!(!between.is_overloaded(3) && between.is_overloaded(4));
+        assert!(Neighbours::Exactly(2).is_overloaded(3));
+        assert!(!Neighbours::AtLeast(2).is_overloaded(100));
+    }
+
+    #[test]
+    fn aggregates() {
+        let values = [3_u64, 5, 2];
+        assert_eq!(Aggregate::Product.apply(values.into_iter()), Some(30));
+        assert_eq!(Aggregate::Sum.apply(values.into_iter()), Some(10));
+        assert_eq!(Aggregate::Max.apply(values.into_iter()), Some(5));
+        assert_eq!(Aggregate::Max.apply(std::iter::empty()), Some(0));
+        assert_eq!(Aggregate::Product.apply([u64::MAX, 2].into_iter()), None);
+        assert_eq!(Aggregate::Sum.apply([u64::MAX, 1].into_iter()), None);
+        assert_eq!("sum".parse(), Ok(Aggregate::Sum));
+        assert_eq!("mean".parse::<Aggregate>(), Err(()));
+    }
+
+    #[test]
+    fn star_with_three_numbers_is_overloaded() {
+        let schematic = Day3::parse(&Input::text("overloaded", "1.2....\n.*...*.\n3...4.5\n")).unwrap();
+        let gears = schematic.gear_ratios(&GearRules::default());
+
+        let overloaded: Vec<char> = gears.overloaded.iter().map(|&i| schematic.symbols[i].c).collect();
+        assert_eq!(overloaded, vec!['*']);
+        assert_eq!(schematic.symbols[gears.overloaded[0]].numbers.len(), 3);
+        assert_eq!(gears.gears.len(), 1);
+        assert_eq!(gears.total(), Some(4 * 5));
+    }
+
+    #[test]
+    fn ratios_past_u64_overflow_the_total() {
+        let huge = "4000000000.\n*4000000000\n4000000000.\n";
+        let schematic = Day3::parse(&Input::text("huge", huge)).unwrap();
+        let rules = GearRules {
+            neighbours: Neighbours::Exactly(3),
+            ..GearRules::default()
+        };
+        let gears = schematic.gear_ratios(&rules);
+        assert_eq!(gears.gears, vec![(0, None)]);
+        assert_eq!(gears.total(), None);
+    }
+
+    #[test]
+    fn total_ratio_past_u64_is_an_error() {
+        let huge = "\
+4000000000..........4000000000
+*...................*.........
+4000000000..........4000000000
+";
+        let input = Input::text("huge", huge);
+        let schematic = Day3::parse(&input).unwrap();
+        let gears = schematic.gear_ratios(&GearRules::default());
+        assert_eq!(gears.gears.len(), 2);
+        assert!(gears.gears.iter().all(|&(_, ratio)| ratio == Some(16_000_000_000_000_000_000)));
+        assert!(matches!(Day3::part2(&input, &schematic), Err(Error::NoSolution { .. })));
     }
 }
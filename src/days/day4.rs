@@ -1,79 +1,448 @@
+use crate::days::answer::Answer;
 use crate::days::solution::Solution;
+use crate::error::{Error, InputLine, Result};
+use crate::generate::Rng;
+use crate::input::Input;
 use std::collections::HashSet;
+use std::str::FromStr;
 
-struct Card {
-    matches: usize,
+/// A scratchcard split into its winning numbers and the numbers we have
+#[derive(Debug, Clone)]
+pub struct Scratchcard {
+    pub id: usize,
+    pub winning: HashSet<u32>,
+    pub have: HashSet<u32>,
 }
 
-pub(crate) struct Day4;
+impl Scratchcard {
+    /// Parse a `Card <id>: <winning> | <have>` line. The ids have to count up from 1
+    /// in the order the cards are listed, and neither side may repeat a number.
+    fn parse(line: &InputLine) -> Result<Self> {
+        let Some((id, rest)) = line
+            .text
+            .strip_prefix("Card")
+            .and_then(|rest| rest.split_once(':'))
+        else {
+            return Err(line.error("expected `Card <id>:`"));
+        };
+        let id = id.trim();
+        if line.number::<usize>(id)? != line.number {
+            return Err(line.error_at(id, format!("expected card {}", line.number)));
+        }
+        let Some((winning, have)) = rest.split_once('|') else {
+            return Err(line.error_at(rest, "expected `|` between the two sets of numbers"));
+        };
 
-impl Solution for Day4 {
-    fn problem1(path: &str) -> std::io::Result<()> {
-        let lines = Self::read_input_into_lines(path)?;
+        Ok(Self {
+            id: line.number,
+            winning: Scratchcard::parse_numbers(line, winning)?,
+            have: Scratchcard::parse_numbers(line, have)?,
+        })
+    }
+
+    fn parse_numbers(line: &InputLine, numbers: &str) -> Result<HashSet<u32>> {
+        let mut set = HashSet::new();
+        for s in numbers.split_whitespace() {
+            if !set.insert(line.number::<u32>(s)?) {
+                return Err(line.error_at(s, "duplicate number"));
+            }
+        }
+        Ok(set)
+    }
 
-        let total = lines
+    /// How many of our numbers are winning numbers
+    pub fn matches(&self) -> usize {
+        self.have.intersection(&self.winning).count()
+    }
+
+    pub fn points(&self, scoring: &Scoring) -> Option<u64> {
+        scoring.points(self.matches())
+    }
+}
+
+/// How many points a card is worth for its number of matches
+#[derive(Debug, Clone, PartialEq)]
+pub enum Scoring {
+    /// A point for every match
+    Linear,
+    /// One point for the first match, doubled for every match after it
+    Doubling,
+    /// The points for 0, 1, 2 and so on matches, with more matches than are listed
+    /// scoring the last entry
+    Table(Vec<u32>),
+}
+
+impl Scoring {
+    /// The points for `matches` matches, or nothing when there are too many to count
+    pub fn points(&self, matches: usize) -> Option<u64> {
+        match self {
+            Scoring::Linear => Some(matches as u64),
+            Scoring::Doubling if matches == 0 => Some(0),
+            Scoring::Doubling => 2_u64.checked_pow(u32::try_from(matches - 1).ok()?),
+            Scoring::Table(points) => Some(points.get(matches).or(points.last()).copied().unwrap_or(0).into()),
+        }
+    }
+
+    /// The points of every card added up, or nothing when there are too many to count
+    pub fn total(&self, cards: &[Scratchcard]) -> Option<u64> {
+        cards
             .iter()
-            .map(|line| {
-                let (_, rest) = line.split_once(':').unwrap();
-                let (winning, have) = rest.split_once('|').unwrap();
-                let winning_nums: HashSet<u32> = winning
-                    .split_whitespace()
-                    .map(|s| s.parse::<u32>().unwrap())
-                    .collect();
-                let have_nums: HashSet<u32> = have
-                    .split_whitespace()
-                    .map(|s| s.parse::<u32>().unwrap())
-                    .collect();
-
-                let winners = have_nums.intersection(&winning_nums).count() as u32;
-                if winners == 0 {
-                    0
-                } else {
-                    (2_u32).pow(winners - 1)
-                }
-            })
-            .sum::<u32>();
+            .try_fold(0_u64, |total, card| total.checked_add(card.points(self)?))
+    }
+}
+
+/// Points that were too many to count are shown as `overflow`
+fn show_count(count: Option<u64>) -> String {
+    count.map_or_else(|| "overflow".to_owned(), |n| n.to_string())
+}
 
-        println!("Got answer for day 4 problem 1: {}", total);
+/// Written as `linear`, `doubling` or a table like `0,1,3,6`
+impl FromStr for Scoring {
+    type Err = ();
 
-        Ok(())
+    fn from_str(s: &str) -> std::result::Result<Self, ()> {
+        match s {
+            "linear" => Ok(Scoring::Linear),
+            "doubling" => Ok(Scoring::Doubling),
+            table => table
+                .split(',')
+                .map(|n| n.trim().parse().map_err(|_| ()))
+                .collect::<std::result::Result<_, _>>()
+                .map(Scoring::Table),
+        }
     }
+}
 
-    fn problem2(path: &str) -> std::io::Result<()> {
-        let lines = Self::read_input_into_lines(path)?;
-        let cards: Vec<Card> = lines
+/// How one card fared in the cascade of part 2
+#[derive(Debug, Clone)]
+pub struct CardTrace {
+    pub card: usize,
+    pub matches: usize,
+    pub points: Option<u64>,
+    /// How many of this card we end up with, the original included, or nothing
+    /// when there are too many to count
+    pub copies: Option<u64>,
+    /// Each earlier card that won copies of this one, with how many it won
+    pub sources: Vec<(usize, Option<u64>)>,
+    /// How many of the cards after this one that it would win copies of are past
+    /// the last card, and so don't exist
+    pub past_end: usize,
+}
+
+/// Every card of the cascade where each card wins a copy of the next cards, one for
+/// each of its matches
+#[derive(Debug, Clone)]
+pub struct Cascade {
+    pub cards: Vec<CardTrace>,
+}
+
+impl Cascade {
+    /// Play every card, scoring each of them with `scoring`
+    pub fn simulate(cards: &[Scratchcard], scoring: &Scoring) -> Self {
+        let mut traces: Vec<CardTrace> = cards
             .iter()
-            .map(|line| {
-                let (_, rest) = line.split_once(':').unwrap();
-
-                let (winning, have) = rest.split_once('|').unwrap();
-                let winning_nums: HashSet<u32> = winning
-                    .split_whitespace()
-                    .map(|s| s.parse::<u32>().unwrap())
-                    .collect();
-                let have_nums: HashSet<u32> = have
-                    .split_whitespace()
-                    .map(|s| s.parse::<u32>().unwrap())
-                    .collect();
-                let matches = have_nums.intersection(&winning_nums).count();
-
-                Card { matches }
+            .enumerate()
+            .map(|(i, card)| CardTrace {
+                card: card.id,
+                matches: card.matches(),
+                points: card.points(scoring),
+                copies: Some(1),
+                sources: vec![],
+                past_end: (i + 1 + card.matches()).saturating_sub(cards.len()),
             })
             .collect();
 
-        let mut card_counts: Vec<u32> = (0..cards.len()).map(|_| 1).collect();
-        for (i, card) in cards.iter().enumerate() {
-            let current_count = card_counts[i];
+        for i in 0..traces.len() {
+            let (won_by, won) = (traces[i].card, traces[i].copies);
+            let last = (i + traces[i].matches).min(traces.len() - 1);
+            for trace in &mut traces[i + 1..=last] {
+                trace.copies = trace.copies.zip(won).and_then(|(copies, won)| copies.checked_add(won));
+                trace.sources.push((won_by, won));
+            }
+        }
+
+        Self { cards: traces }
+    }
+
+    /// How many cards we end up with, or nothing when there are too many to count
+    pub fn total(&self) -> Option<u64> {
+        self.cards
+            .iter()
+            .try_fold(0_u64, |total, c| total.checked_add(c.copies?))
+    }
+
+    /// A row per card with its sources as `<card>x<copies>` separated by spaces
+    pub fn to_csv(&self) -> String {
+        let mut out = "card,matches,points,copies,sources,past_end\n".to_owned();
+        for c in &self.cards {
+            let sources: Vec<String> = c
+                .sources
+                .iter()
+                .map(|(card, n)| format!("{}x{}", card, show_count(*n)))
+                .collect();
+            out += &format!(
+                "{},{},{},{},{},{}\n",
+                c.card,
+                c.matches,
+                show_count(c.points),
+                show_count(c.copies),
+                sources.join(" "),
+                c.past_end
+            );
+        }
+        out
+    }
 
-            for j in i + 1..i + 1 + card.matches {
-                card_counts[j] += current_count;
+    /// A Graphviz graph with a node per card and an edge to every card it won
+    /// copies of, labelled with how many
+    pub fn to_dot(&self) -> String {
+        let mut out = "digraph cascade {\n    rankdir=LR;\n".to_owned();
+        for c in &self.cards {
+            out += &format!(
+                "    card{} [label=\"Card {}\\n{} matches, {} points\\n{} copies\"];\n",
+                c.card,
+                c.card,
+                c.matches,
+                show_count(c.points),
+                show_count(c.copies)
+            );
+        }
+        for c in &self.cards {
+            for (source, n) in &c.sources {
+                out += &format!("    card{} -> card{} [label=\"{}\"];\n", source, c.card, show_count(*n));
             }
         }
+        out + "}\n"
+    }
+}
+
+pub fn print_scores(cards: &[Scratchcard], scoring: &Scoring) {
+    for card in cards {
+        println!(
+            "Card {}: {} matches, {} points",
+            card.id,
+            card.matches(),
+            show_count(card.points(scoring))
+        );
+    }
+    println!("{} points in total", show_count(scoring.total(cards)));
+}
 
-        let total: u32 = card_counts.iter().sum::<u32>();
+pub fn print_cascade(cascade: &Cascade) {
+    for c in &cascade.cards {
+        let sources: Vec<String> = c
+            .sources
+            .iter()
+            .map(|(card, n)| format!("{} from card {}", show_count(*n), card))
+            .collect();
+        let sources = if sources.is_empty() { "the original only".to_owned() } else { sources.join(", ") };
+        print!(
+            "Card {}: {} matches, {} points, {} copies ({})",
+            c.card,
+            c.matches,
+            show_count(c.points),
+            show_count(c.copies),
+            sources
+        );
+        match c.past_end {
+            0 => println!(),
+            n => println!(", {} past the last card", n),
+        }
+    }
+    println!("{} cards in total", show_count(cascade.total()));
+}
+
+pub struct Day4;
+
+impl Solution for Day4 {
+    const DAY: u32 = 4;
+    const TITLE: &'static str = "Scratchcards";
+
+    type Parsed = Vec<Scratchcard>;
+
+    fn parse(input: &Input) -> Result<Vec<Scratchcard>> {
+        let lines = Self::read_input_into_lines(input)?;
+        lines
+            .iter()
+            .enumerate()
+            .map(|(i, l)| Scratchcard::parse(&InputLine::new(input.name(), i, l)))
+            .collect()
+    }
+
+    fn part1(input: &Input, cards: &Vec<Scratchcard>) -> Result<Answer> {
+        let total = Scoring::Doubling.total(cards);
+        Ok(total.ok_or_else(|| Error::no_solution(input.name(), "too many points to count"))?.into())
+    }
+
+    fn part2(input: &Input, cards: &Vec<Scratchcard>) -> Result<Answer> {
+        let total = Cascade::simulate(cards, &Scoring::Doubling).total();
+        Ok(total.ok_or_else(|| Error::no_solution(input.name(), "too many cards to count"))?.into())
+    }
+
+    fn generate(rng: &mut Rng, size: usize) -> String {
+        const WINNING: usize = 5;
+        const HAVE: usize = 8;
+
+        let mut out = String::new();
+        for id in 1..=size {
+            let mut numbers: Vec<u32> = (1..=99).collect();
+            rng.shuffle(&mut numbers);
+            let (winning, rest) = numbers.split_at(WINNING);
+
+            // mostly losing cards so that the copies in part 2 don't grow without bound,
+            // and never more matches than there are cards left to copy
+            let matches = if rng.chance(0.7) { 0 } else { rng.usize(1..=3) };
+            let matches = matches.min(size - id);
+            let mut have = [&winning[..matches], &rest[..HAVE - matches]].concat();
+            rng.shuffle(&mut have);
+
+            let format = |numbers: &[u32]| numbers.iter().map(|n| format!("{:>2}", n)).collect::<Vec<_>>().join(" ");
+            out += &format!("Card {:>3}: {} | {}\n", id, format(winning), format(&have));
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "\
+Card 1: 41 48 83 86 17 | 83 86  6 31 17  9 48 53
+Card 2: 13 32 20 16 61 | 61 30 68 82 17 32 24 19
+Card 3:  1 21 53 59 44 | 69 82 63 72 16 21 14  1
+Card 4: 41 92 73 84 69 | 59 84 76 51 58  5 54 83
+Card 5: 87 83 26 28 32 | 88 30 70 12 93 22 82 36
+Card 6: 31 18 13 56 72 | 74 77 10 23 35 67 36 11
+";
+
+    #[test]
+    fn part1_example() {
+        let input = Input::text("example", EXAMPLE);
+        assert_eq!(Day4::problem1(&input).unwrap(), Answer::Int(13));
+    }
+
+    #[test]
+    fn part2_example() {
+        let input = Input::text("example", EXAMPLE);
+        assert_eq!(Day4::problem2(&input).unwrap(), Answer::Int(30));
+    }
+
+    /// A card where every one of the numbers 1 to `matches` is a winning number
+    fn card_with_matches(id: usize, matches: u32) -> String {
+        let numbers: Vec<String> = (1..=matches).map(|n| n.to_string()).collect();
+        format!("Card {}: {} | {}\n", id, numbers.join(" "), numbers.join(" "))
+    }
+
+    #[test]
+    fn doubling_points() {
+        assert_eq!(Scoring::Doubling.points(0), Some(0));
+        assert_eq!(Scoring::Doubling.points(1), Some(1));
+        assert_eq!(Scoring::Doubling.points(4), Some(8));
+        assert_eq!(Scoring::Doubling.points(34), Some(1 << 33));
+        assert_eq!(Scoring::Doubling.points(64), Some(1 << 63));
+        assert_eq!(Scoring::Doubling.points(65), None);
+    }
+
+    #[test]
+    fn table_points_past_the_end_score_the_last_entry() {
+        let table: Scoring = "0,1,3,6".parse().unwrap();
+        assert_eq!(table.points(2), Some(3));
+        assert_eq!(table.points(10), Some(6));
+        assert_eq!("".parse::<Scoring>(), Err(()));
+    }
+
+    #[test]
+    fn many_matches_do_not_overflow() {
+        let input = Input::text("many", &card_with_matches(1, 34));
+        assert_eq!(Day4::problem1(&input).unwrap(), Answer::Int(1 << 33));
+    }
+
+    #[test]
+    fn too_many_points_are_an_error() {
+        let input = Input::text("too many", &(card_with_matches(1, 64) + &card_with_matches(2, 64)));
+        assert!(matches!(Day4::problem1(&input), Err(Error::NoSolution { .. })));
+
+        let cards = Day4::parse(&input).unwrap();
+        assert_eq!(Scoring::Doubling.total(&cards), None);
+        assert_eq!(Scoring::Linear.total(&cards), Some(128));
+    }
+
+    fn cascade(text: &str) -> Cascade {
+        let cards = Day4::parse(&Input::text("cascade", text)).unwrap();
+        Cascade::simulate(&cards, &Scoring::Doubling)
+    }
+
+    #[test]
+    fn cascade_of_the_example() {
+        let cascade = cascade(EXAMPLE);
+        assert_eq!(cascade.total(), Some(30));
+
+        let copies: Vec<u64> = cascade.cards.iter().map(|c| c.copies.unwrap()).collect();
+        assert_eq!(copies, vec![1, 2, 4, 8, 14, 1]);
+        assert_eq!(cascade.cards[4].sources, vec![(1, Some(1)), (3, Some(4)), (4, Some(8))]);
+        assert!(cascade.cards[5].sources.is_empty());
+        assert!(cascade.cards.iter().all(|c| c.past_end == 0));
+    }
+
+    #[test]
+    fn cascade_stops_at_the_last_card() {
+        let cascade = cascade("Card 1: 1 2 | 1 3\nCard 2: 5 6 7 | 5 6 8\n");
+        assert_eq!(cascade.cards[0].past_end, 0);
+        assert_eq!(cascade.cards[1].matches, 2);
+        assert_eq!(cascade.cards[1].past_end, 2);
+        assert_eq!(cascade.cards[1].sources, vec![(1, Some(1))]);
+        assert_eq!(cascade.total(), Some(3));
+    }
+
+    #[test]
+    fn cascade_of_no_cards() {
+        let cascade = Cascade::simulate(&[], &Scoring::Doubling);
+        assert!(cascade.cards.is_empty());
+        assert_eq!(cascade.total(), Some(0));
+        assert_eq!(cascade.to_csv(), "card,matches,points,copies,sources,past_end\n");
+        assert_eq!(cascade.to_dot(), "digraph cascade {\n    rankdir=LR;\n}\n");
+    }
+
+    #[test]
+    fn cascade_as_csv() {
+        let csv = cascade(EXAMPLE).to_csv();
+        let rows: Vec<&str> = csv.lines().collect();
+        assert_eq!(
+            rows,
+            vec![
+                "card,matches,points,copies,sources,past_end",
+                "1,4,8,1,,0",
+                "2,2,2,2,1x1,0",
+                "3,2,2,4,1x1 2x2,0",
+                "4,1,1,8,1x1 2x2 3x4,0",
+                "5,0,0,14,1x1 3x4 4x8,0",
+                "6,0,0,1,,0",
+            ]
+        );
+    }
+
+    #[test]
+    fn cascade_as_dot() {
+        let dot = cascade("Card 1: 1 2 | 1 3\nCard 2: 5 6 7 | 5 6 8\n").to_dot();
+        assert!(dot.starts_with("digraph cascade {\n"));
+        assert!(dot.contains("    card2 [label=\"Card 2\\n2 matches, 2 points\\n2 copies\"];\n"));
+        let edges: Vec<&str> = dot.lines().filter(|l| l.contains("->")).collect();
+        assert_eq!(edges, vec!["    card1 -> card2 [label=\"1\"];"]);
+        assert!(dot.ends_with("}\n"));
+    }
 
-        println!("Got answer for day 4 problem 2: {}", total);
+    #[test]
+    fn too_many_copies_are_an_error() {
+        let text: String = (1..=70).map(|id| card_with_matches(id, 70)).collect();
+        let input = Input::text("too many", &text);
+        assert!(matches!(Day4::problem2(&input), Err(Error::NoSolution { .. })));
 
-        Ok(())
+        let cascade = cascade(&text);
+        assert_eq!(cascade.cards[63].copies, Some(1 << 63));
+        assert_eq!(cascade.cards[64].copies, None);
+        assert_eq!(cascade.cards[65].sources[64], (65, None));
+        assert_eq!(cascade.total(), None);
+        assert!(cascade.to_csv().lines().nth(65).unwrap().starts_with("65,70,overflow,overflow,"));
     }
 }
@@ -1,8 +1,10 @@
+use super::answer::Answer;
 use super::solution::Solution;
+use crate::error::{Error, InputLine, Result};
+use crate::generate::Rng;
+use crate::input::Input;
 use lazy_static::lazy_static;
 use regex::Regex;
-use std::fs::File;
-use std::io::{BufRead, BufReader, Result};
 use std::ops::Range as StdRange;
 
 lazy_static! {
@@ -12,10 +14,11 @@ lazy_static! {
     static ref SEED_RANGE_RE: Regex = Regex::new(r"(?P<start>\d+) (?P<len>\d+)").unwrap();
 }
 
-#[derive(Debug)]
-struct Range {
-    start: u64,
-    end: u64,
+/// A half open range of numbers
+#[derive(Debug, Clone)]
+pub struct Range {
+    pub start: u64,
+    pub end: u64,
 }
 
 impl Range {
@@ -74,23 +77,26 @@ impl Range {
     }
 }
 
-#[derive(Debug)]
-struct RangeMap {
-    src: Range,
-    dest: Range,
+/// A single `<dest> <src> <len>` line of a map
+#[derive(Debug, Clone)]
+pub struct RangeMap {
+    pub src: Range,
+    pub dest: Range,
 }
 
 impl RangeMap {
-    fn from_line(line: &str) -> Self {
-        let capt = LINE_RE.captures(line).unwrap();
-        let src_start = capt.name("src").unwrap().as_str().parse::<u64>().unwrap();
-        let dest_start = capt.name("dest").unwrap().as_str().parse::<u64>().unwrap();
-        let len = capt.name("len").unwrap().as_str().parse::<u64>().unwrap();
+    fn from_line(line: &InputLine) -> Result<Self> {
+        let Some(capt) = LINE_RE.captures(line.text) else {
+            return Err(line.error("expected `<dest> <src> <len>`"));
+        };
+        let src_start = line.number::<u64>(&capt["src"])?;
+        let dest_start = line.number::<u64>(&capt["dest"])?;
+        let len = line.number::<u64>(&capt["len"])?;
 
         let src = Range::new(src_start, src_start + len);
         let dest = Range::new(dest_start, dest_start + len);
 
-        Self { src, dest }
+        Ok(Self { src, dest })
     }
 
     fn apply(&self, r: &Range) -> (Option<Range>, Option<Range>, Option<Range>) {
@@ -124,16 +130,15 @@ impl RangeMap {
     }
 }
 
-#[derive(Debug)]
-struct MapList {
-    ranges: Vec<RangeMap>,
+/// One of the `<name> map:` sections of the almanac
+#[derive(Debug, Clone)]
+pub struct MapList {
+    pub ranges: Vec<RangeMap>,
 }
 
 impl MapList {
-    fn new(lines: Vec<String>) -> Self {
-        let ranges = lines.iter().map(|l| RangeMap::from_line(l)).collect();
-
-        Self { ranges }
+    fn new() -> Self {
+        Self { ranges: vec![] }
     }
 
     fn get_dest(&self, n: u64) -> u64 {
@@ -156,6 +161,28 @@ impl MapList {
         n
     }
 
+    /// Whether every number maps to a different one, so that the map can be walked
+    /// backwards. Numbers outside of every source map to themselves, so the
+    /// destinations have to cover exactly the numbers the sources do.
+    fn is_one_to_one(&self) -> bool {
+        let covered = |mut ranges: Vec<&Range>| {
+            ranges.sort_by_key(|r| r.start);
+            let mut covered: Vec<Range> = vec![];
+            for r in ranges {
+                match covered.last_mut() {
+                    _ if r.start >= r.end => return None,
+                    Some(last) if last.end > r.start => return None,
+                    Some(last) if last.end == r.start => last.end = r.end,
+                    _ => covered.push(r.clone()),
+                }
+            }
+            Some(covered.into_iter().map(|r| (r.start, r.end)).collect::<Vec<_>>())
+        };
+
+        let src = covered(self.ranges.iter().map(|r| &r.src).collect());
+        src.is_some() && src == covered(self.ranges.iter().map(|r| &r.dest).collect())
+    }
+
     fn has_any_overlap(&self, range: &Range) -> bool {
         self.ranges.iter().any(|r| r.has_overlap(range))
     }
@@ -186,73 +213,73 @@ impl MapList {
     }
 }
 
+/// The parsed almanac. The seeds line is read both as single seeds for part 1 and as
+/// `<start> <len>` pairs for part 2.
+#[derive(Debug, Clone)]
+pub struct Almanac {
+    pub seeds: Vec<Range>,
+    pub seed_ranges: Vec<Range>,
+    pub maps: Vec<MapList>,
+}
+
 pub struct Day5;
 
 impl Day5 {
-    fn parse_seeds_1(line: &str) -> Vec<Range> {
+    fn parse_seeds_1(line: &InputLine) -> Result<Vec<Range>> {
         SEED_RE
-            .captures_iter(line)
-            .map(|capt| {
-                let start = capt.get(0).unwrap().as_str().parse::<u64>().unwrap();
-                Range::new(start, start + 1)
+            .find_iter(line.text)
+            .map(|m| {
+                let start = line.number::<u64>(m.as_str())?;
+                Ok(Range::new(start, start + 1))
             })
             .collect()
     }
 
-    fn parse_seeds_2(line: &str) -> Vec<Range> {
+    fn parse_seeds_2(line: &InputLine) -> Result<Vec<Range>> {
         SEED_RANGE_RE
-            .captures_iter(line)
+            .captures_iter(line.text)
             .map(|capt| {
-                let start = capt.name("start").unwrap().as_str().parse::<u64>().unwrap();
-                let len = capt.name("len").unwrap().as_str().parse::<u64>().unwrap();
-                Range::new(start, start + len)
+                let start = line.number::<u64>(&capt["start"])?;
+                let len = line.number::<u64>(&capt["len"])?;
+                Ok(Range::new(start, start + len))
             })
             .collect()
     }
 
-    fn parse_file(
-        path: &str,
-        parse_seeds_f: fn(&str) -> Vec<Range>,
-    ) -> Result<(Vec<Range>, Vec<MapList>)> {
-        let f = File::open(path)?;
-        let mut reader = BufReader::new(f);
-
-        let mut line = String::new();
+    fn parse_file(input: &Input) -> Result<Almanac> {
+        let path = input.name();
         let mut seeds = Vec::new();
-        let mut maps = Vec::new();
-        loop {
-            let n = reader.read_line(&mut line).unwrap();
-            if n == 0 {
-                break;
-            }
-            match &line.trim() {
-                l if l.starts_with("seeds:") => {
-                    seeds.extend(parse_seeds_f(l));
-                }
-                l if HEADER_RE.is_match(l) => {
-                    let mut map_lines = Vec::new();
-                    let mut map_line = String::new();
-
-                    reader.read_line(&mut map_line).unwrap();
-                    while !map_line.trim().is_empty() {
-                        map_lines.push(map_line.clone());
-                        map_line.clear();
-                        reader.read_line(&mut map_line).unwrap();
+        let mut seed_ranges = Vec::new();
+        let mut maps: Vec<MapList> = Vec::new();
+        // The seeds and each map are in their own block, and every map block starts
+        // with its header
+        for block in Day5::input_blocks(input)? {
+            let block = block?;
+            for (i, text) in block.numbered() {
+                let line = InputLine::new(path, i, text);
+                match line.text.trim() {
+                    l if l.starts_with("seeds:") => {
+                        seeds.extend(Day5::parse_seeds_1(&line)?);
+                        seed_ranges.extend(Day5::parse_seeds_2(&line)?);
                     }
-
-                    let map = MapList::new(map_lines);
-                    maps.push(map);
+                    l if HEADER_RE.is_match(l) && i == block.start => maps.push(MapList::new()),
+                    _ if i == block.start => return Err(line.error("expected `seeds:` or a map header")),
+                    _ => match maps.last_mut() {
+                        Some(map) => map.ranges.push(RangeMap::from_line(&line)?),
+                        None => return Err(line.error("expected a map header before the ranges")),
+                    },
                 }
-                l if l.trim().is_empty() => continue,
-                _ => panic!(),
             }
-            line.clear();
         }
 
-        Ok((seeds, maps))
+        Ok(Almanac {
+            seeds,
+            seed_ranges,
+            maps,
+        })
     }
 
-    fn p2_brute(seeds: Vec<Range>, maps: Vec<MapList>) -> Option<u64> {
+    fn p2_brute(seeds: &[Range], maps: &[MapList]) -> Option<u64> {
         let mut min = None;
         for i in 0.. {
             let mut res = i;
@@ -260,7 +287,7 @@ impl Day5 {
                 res = map.get_src(res);
             }
 
-            for seed in &seeds {
+            for seed in seeds {
                 if seed.contains(res) {
                     min = Some(i);
                     break;
@@ -273,49 +300,166 @@ impl Day5 {
         min
     }
 
-    fn p2_new(seeds: Vec<Range>, maps: Vec<MapList>) -> Option<u64> {
+    fn p2_new(seeds: &[Range], maps: &[MapList]) -> Option<u64> {
         let mut mins = vec![];
 
         for seed in seeds {
-            let mut ranges = vec![seed];
-            for map in &maps {
+            let mut ranges = vec![seed.clone()];
+            for map in maps {
                 ranges = map.apply(ranges);
             }
-            let min = ranges.iter().map(|r| r.start).min();
-            mins.push(min.unwrap());
+            mins.extend(ranges.iter().map(|r| r.start).min());
         }
 
         mins.into_iter().min()
     }
+
+    /// Part 2 by brute force, trying every location from 0 up until one walks back to
+    /// a seed. Only inputs whose maps are one to one can be walked back like that.
+    pub fn part2_brute(input: &Input) -> Result<Answer> {
+        let path = input.name();
+        let almanac = Day5::parse(input)?;
+        if almanac.seed_ranges.is_empty() || almanac.seed_ranges.iter().any(|r| r.start >= r.end) {
+            return Err(Error::no_solution(path, "no seeds or an empty seed range"));
+        }
+        if !almanac.maps.iter().all(MapList::is_one_to_one) {
+            return Err(Error::no_solution(path, "a map is not one to one"));
+        }
+
+        let min = Day5::p2_brute(&almanac.seed_ranges, &almanac.maps);
+        Ok(min.ok_or_else(|| Error::no_solution(path, "no seeds"))?.into())
+    }
 }
 
 impl Solution for Day5 {
-    fn problem1(path: &str) -> Result<()> {
-        let (seeds, maps) = Day5::parse_file(path, Day5::parse_seeds_1)?;
+    const DAY: u32 = 5;
+    const TITLE: &'static str = "If You Give A Seed A Fertilizer";
+
+    type Parsed = Almanac;
+
+    fn parse(input: &Input) -> Result<Almanac> {
+        Day5::parse_file(input)
+    }
+
+    fn part1(input: &Input, almanac: &Almanac) -> Result<Answer> {
+        let path = input.name();
         let mut results = vec![];
 
-        for seed_range in &seeds {
+        for seed_range in &almanac.seeds {
             for seed in seed_range.rng() {
                 let mut res = seed;
-                for map in &maps {
+                for map in &almanac.maps {
                     res = map.get_dest(res);
                 }
                 results.push(res);
             }
         }
 
-        println!(
-            "Got answer for Day 5 Problem 1: {}",
-            results.iter().min().unwrap()
-        );
+        let min = results.iter().min().copied();
+        Ok(min.ok_or_else(|| Error::no_solution(path, "no seeds"))?.into())
+    }
+
+    fn part2(input: &Input, almanac: &Almanac) -> Result<Answer> {
+        let path = input.name();
+        let min = Day5::p2_new(&almanac.seed_ranges, &almanac.maps);
+        Ok(min.ok_or_else(|| Error::no_solution(path, "no seeds"))?.into())
+    }
+
+    fn generate(rng: &mut Rng, size: usize) -> String {
+        const MAPS: [&str; 7] = [
+            "seed-to-soil",
+            "soil-to-fertilizer",
+            "fertilizer-to-water",
+            "water-to-light",
+            "light-to-temperature",
+            "temperature-to-humidity",
+            "humidity-to-location",
+        ];
+        let size = size as u64;
+        let limit = 100 * size;
+
+        let mut seeds = vec![];
+        for _ in 0..rng.range(1..=size.div_ceil(2)) {
+            let start = rng.below(limit);
+            seeds.push(start);
+            seeds.push(rng.range(1..=(limit - start).min(limit / 10).max(1)));
+        }
+        let seeds: Vec<String> = seeds.iter().map(u64::to_string).collect();
+        let mut out = format!("seeds: {}\n", seeds.join(" "));
+
+        // Every map cuts `0..limit` into pieces and moves them around, so that it is a
+        // one to one mapping and can be walked backwards like the brute force does
+        for name in MAPS {
+            let mut cuts: Vec<u64> = (0..rng.range(1..=size)).map(|_| rng.range(1..=limit - 1)).collect();
+            cuts.extend([0, limit]);
+            cuts.sort();
+            cuts.dedup();
+            let mut pieces: Vec<(u64, u64)> = cuts.windows(2).map(|w| (w[0], w[1] - w[0])).collect();
+            rng.shuffle(&mut pieces);
+
+            let mut dest = 0;
+            let mut lines = vec![];
+            for (src, len) in pieces {
+                lines.push(format!("{} {} {}", dest, src, len));
+                dest += len;
+            }
+            rng.shuffle(&mut lines);
+
+            out += &format!("\n{} map:\n{}\n", name, lines.join("\n"));
+        }
+        out
+    }
+}
 
-        Ok(())
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "\
+seeds: 79 14 55 13
+
+seed-to-soil map:
+50 98 2
+52 50 48
+
+soil-to-fertilizer map:
+0 15 37
+37 52 2
+39 0 15
+
+fertilizer-to-water map:
+49 53 8
+0 11 42
+42 0 7
+57 7 4
+
+water-to-light map:
+88 18 7
+18 25 70
+
+light-to-temperature map:
+45 77 23
+81 45 19
+68 64 13
+
+temperature-to-humidity map:
+0 69 1
+1 0 69
+
+humidity-to-location map:
+60 56 37
+56 93 4
+";
+
+    #[test]
+    fn part1_example() {
+        let input = Input::text("example", EXAMPLE);
+        assert_eq!(Day5::problem1(&input).unwrap(), Answer::Int(35));
     }
 
-    fn problem2(path: &str) -> Result<()> {
-        let (seeds, maps) = Day5::parse_file(path, Day5::parse_seeds_2)?;
-        let min = Day5::p2_new(seeds, maps);
-        println!("Got answer to Day 5 Problem 2: {}", min.unwrap());
-        Ok(())
+    #[test]
+    fn part2_example() {
+        let input = Input::text("example", EXAMPLE);
+        assert_eq!(Day5::problem2(&input).unwrap(), Answer::Int(46));
     }
 }
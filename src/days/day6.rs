@@ -1,17 +1,29 @@
+use super::answer::Answer;
 use super::solution::Solution;
+use crate::error::{InputLine, Result};
+use crate::generate::Rng;
+use crate::input::Input;
 use regex::Regex;
-use std::fs::File;
-use std::io::{BufRead, BufReader, Result};
 
 pub struct Day6;
 
-struct Race {
-    time: u64,
-    distance: u64,
+#[derive(Debug, Clone)]
+pub struct Race {
+    pub time: u64,
+    pub distance: u64,
+}
+
+/// The races on the sheet. Part 2 reads the sheet as a single race with the spaces
+/// between the digits removed.
+#[derive(Debug, Clone)]
+pub struct Races {
+    pub races: Vec<Race>,
+    pub single: Race,
 }
 
 impl Race {
-    fn calc_wins(&self) -> usize {
+    /// How many ways there are to beat the record distance
+    pub fn calc_wins(&self) -> usize {
         (1..self.time)
             .filter(|i| (self.time - i) * i > self.distance)
             .count()
@@ -19,37 +31,44 @@ impl Race {
 }
 
 impl Day6 {
-    fn parse_races(path: &str) -> Result<Vec<Race>> {
-        let f = File::open(path)?;
+    /// The time and distance lines, which are empty when the input is too short
+    fn read_lines(input: &Input) -> Result<(String, String)> {
+        let mut lines = Day6::input_lines(input)?;
+        let time = lines.next().transpose()?.unwrap_or_default();
+        let distance = lines.next().transpose()?.unwrap_or_default();
+        Ok((time, distance))
+    }
+
+    fn parse_races(input: &Input) -> Result<Vec<Race>> {
+        let path = input.name();
         let num_re = Regex::new(r"\d+").unwrap();
-        let mut reader = BufReader::new(f);
-        let mut time = String::new();
-        let mut distance = String::new();
-
-        reader.read_line(&mut time)?;
-        reader.read_line(&mut distance)?;
-
-        let races = num_re
-            .captures_iter(&time)
-            .map(|c| c.get(0).unwrap().as_str().parse::<u64>().unwrap())
-            .zip(
-                num_re
-                    .captures_iter(&distance)
-                    .map(|c| c.get(0).unwrap().as_str().parse::<u64>().unwrap()),
-            )
+        let (time, distance) = Day6::read_lines(input)?;
+
+        let time_line = InputLine::new(path, 0, &time);
+        let distance_line = InputLine::new(path, 1, &distance);
+        let times = num_re
+            .find_iter(&time)
+            .map(|m| time_line.number::<u64>(m.as_str()))
+            .collect::<Result<Vec<u64>>>()?;
+        let distances = num_re
+            .find_iter(&distance)
+            .map(|m| distance_line.number::<u64>(m.as_str()))
+            .collect::<Result<Vec<u64>>>()?;
+        if times.len() != distances.len() {
+            return Err(distance_line.error("expected one distance for every time"));
+        }
+
+        let races = times
+            .into_iter()
+            .zip(distances)
             .map(|(time, distance)| Race { time, distance })
             .collect();
 
         Ok(races)
     }
-    fn parse_race(path: &str) -> Result<Race> {
-        let f = File::open(path)?;
-        let mut reader = BufReader::new(f);
-        let mut time_parts = String::new();
-        let mut distance_parts = String::new();
-
-        reader.read_line(&mut time_parts)?;
-        reader.read_line(&mut distance_parts)?;
+    fn parse_race(input: &Input) -> Result<Race> {
+        let path = input.name();
+        let (time_parts, distance_parts) = Day6::read_lines(input)?;
 
         let time_str = time_parts
             .chars()
@@ -61,8 +80,12 @@ impl Day6 {
             .filter(|c| !c.is_whitespace())
             .collect::<String>()
             .replace("Distance:", "");
-        let time = time_str.parse::<u64>().unwrap();
-        let distance = distance_str.parse::<u64>().unwrap();
+        let time = time_str
+            .parse::<u64>()
+            .map_err(|_| InputLine::new(path, 0, &time_parts).error("expected `Time:` followed by digits"))?;
+        let distance = distance_str.parse::<u64>().map_err(|_| {
+            InputLine::new(path, 1, &distance_parts).error("expected `Distance:` followed by digits")
+        })?;
 
         let race = Race { time, distance };
 
@@ -71,18 +94,65 @@ impl Day6 {
 }
 
 impl Solution for Day6 {
-    fn problem1(path: &str) -> std::io::Result<()> {
-        let races = Day6::parse_races(path)?;
-        let res = races.iter().fold(1, |acc, r| r.calc_wins() * acc);
-        println!("Got solution for Day 6 problem 1: {}", res);
-        Ok(())
+    const DAY: u32 = 6;
+    const TITLE: &'static str = "Wait For It";
+
+    type Parsed = Races;
+
+    fn parse(input: &Input) -> Result<Races> {
+        Ok(Races {
+            races: Day6::parse_races(input)?,
+            single: Day6::parse_race(input)?,
+        })
+    }
+
+    fn part1(_input: &Input, races: &Races) -> Result<Answer> {
+        let res = races.races.iter().fold(1, |acc, r| r.calc_wins() * acc);
+        Ok(res.into())
     }
 
-    fn problem2(path: &str) -> std::io::Result<()> {
-        let race = Day6::parse_race(path)?;
-        let wins = race.calc_wins();
-        println!("Got solution for Day 6 problem 2: {}", wins);
+    fn part2(_input: &Input, races: &Races) -> Result<Answer> {
+        let wins = races.single.calc_wins();
+        Ok(wins.into())
+    }
+
+    fn generate(rng: &mut Rng, size: usize) -> String {
+        // part 2 joins every race into one, so a few races are already a long race
+        let races: Vec<(u64, u64)> = (0..size.clamp(1, 4))
+            .map(|_| {
+                let time = rng.range(7..=99);
+                let best = (time / 2) * (time - time / 2);
+                (time, rng.range(1..=best - 1))
+            })
+            .collect();
+
+        let column = |values: Vec<u64>| values.iter().map(|v| format!(" {:>5}", v)).collect::<String>();
+        format!(
+            "Time:    {}\nDistance:{}\n",
+            column(races.iter().map(|r| r.0).collect()),
+            column(races.iter().map(|r| r.1).collect()),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "\
+Time:      7  15   30
+Distance:  9  40  200
+";
+
+    #[test]
+    fn part1_example() {
+        let input = Input::text("example", EXAMPLE);
+        assert_eq!(Day6::problem1(&input).unwrap(), Answer::Int(288));
+    }
 
-        Ok(())
+    #[test]
+    fn part2_example() {
+        let input = Input::text("example", EXAMPLE);
+        assert_eq!(Day6::problem2(&input).unwrap(), Answer::Int(71503));
     }
 }
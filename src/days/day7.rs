@@ -1,17 +1,20 @@
+use super::answer::Answer;
 use super::solution::Solution;
 use lazy_static::lazy_static;
 use regex::Regex;
 use std::cmp::Ordering;
+use crate::error::{InputLine, Result};
+use crate::generate::Rng;
+use crate::input::Input;
+use crate::trace;
 use std::collections::HashMap;
-use std::fs::File;
-use std::io::{BufRead, BufReader, Result};
 
 lazy_static! {
     static ref LINE_RE: Regex = Regex::new(r"(?P<hand>[AKQJT2-9]+) (?P<bid>\d+)").unwrap();
 }
 
-fn get_value(c: char) -> u32 {
-    match c {
+fn get_value(c: char) -> Option<u32> {
+    let value = match c {
         'A' => 14,
         'K' => 13,
         'Q' => 12,
@@ -25,12 +28,13 @@ fn get_value(c: char) -> u32 {
         '4' => 4,
         '3' => 3,
         '2' => 2,
-        _ => panic!(),
-    }
+        _ => return None,
+    };
+    Some(value)
 }
 
-fn get_value2(c: char) -> u32 {
-    match c {
+fn get_value2(c: char) -> Option<u32> {
+    let value = match c {
         'A' => 13,
         'K' => 12,
         'Q' => 11,
@@ -44,75 +48,93 @@ fn get_value2(c: char) -> u32 {
         '3' => 3,
         '2' => 2,
         'J' => 1,
-        _ => panic!(),
-    }
+        _ => return None,
+    };
+    Some(value)
 }
 
+/// A hand with its cards converted to their values
 #[derive(Debug, Clone)]
-struct Hand {
-    cards: Vec<u32>,
-    bid: u32,
+pub struct Hand {
+    pub cards: Vec<u32>,
+    pub bid: u32,
     score: Option<u32>,
 }
 
-fn counts<I>(mut iter: I) -> HashMap<u32, usize>
+/// The hands valued under both sets of rules, in part 2 `J` is a joker worth the least
+#[derive(Debug, Clone)]
+pub struct Hands {
+    pub hands: Vec<Hand>,
+    pub joker_hands: Vec<Hand>,
+}
+
+fn counts<I>(iter: I) -> HashMap<u32, usize>
 where
     I: Iterator<Item = u32>,
 {
     let mut counts = HashMap::new();
-    while let Some(n) = iter.next() {
-        if counts.contains_key(&n) {
-            counts.get_mut(&n).map(|c| *c += 1);
-        } else {
-            counts.insert(n, 1);
-        }
+    for n in iter {
+        *counts.entry(n).or_insert(0) += 1;
     }
 
     counts
 }
 
 impl Hand {
-    fn new(data: String, val_func: fn(char) -> u32) -> Self {
-        let parsed = LINE_RE.captures(&data).unwrap();
+    fn new(line: &InputLine, val_func: fn(char) -> Option<u32>) -> Result<Self> {
+        let Some(parsed) = LINE_RE.captures(line.text) else {
+            return Err(line.error("expected `<hand> <bid>`"));
+        };
 
-        let cards = parsed
-            .name("hand")
-            .unwrap()
-            .as_str()
+        let hand = &parsed["hand"];
+        let cards = hand
             .chars()
             .map(val_func)
-            .collect();
-        let bid = parsed.name("bid").unwrap().as_str().parse::<u32>().unwrap();
+            .collect::<Option<Vec<u32>>>()
+            .ok_or_else(|| line.error_at(hand, "unknown card in hand"))?;
+        let bid = line.number::<u32>(&parsed["bid"])?;
 
-        Self {
+        Ok(Self {
             cards,
             bid,
             score: None,
-        }
+        })
     }
 
-    fn compute_score(&self) -> u32 {
-        let card_counts = counts(self.cards.iter().cloned());
-        let max_count = card_counts.values().max().unwrap();
-        let len = card_counts.len();
-
-        if *max_count == 5 {
+    /// The type of a hand from how many of its most common card it has and how many
+    /// different cards it has
+    fn hand_type(max_count: usize, kinds: usize) -> u32 {
+        if max_count == 5 {
             7
-        } else if *max_count == 4 {
+        } else if max_count == 4 {
             6
-        } else if *max_count == 3 && len == 2 {
+        } else if max_count == 3 && kinds == 2 {
             5
-        } else if *max_count == 3 {
+        } else if max_count == 3 {
             4
-        } else if *max_count == 2 && len == 3 {
+        } else if max_count == 2 && kinds == 3 {
             3
-        } else if *max_count == 2 {
+        } else if max_count == 2 {
             2
         } else {
             1
         }
     }
 
+    fn compute_score(&self) -> u32 {
+        let card_counts = counts(self.cards.iter().cloned());
+        let max_count = card_counts.values().max().unwrap();
+        Hand::hand_type(*max_count, card_counts.len())
+    }
+
+    /// Jokers always make the best hand by joining the most common of the other cards
+    fn compute_joker_score(&self) -> u32 {
+        let card_counts = counts(self.cards.iter().cloned().filter(|c| *c != 1));
+        let jokers = self.cards.len() - card_counts.values().sum::<usize>();
+        let max_count = card_counts.values().max().unwrap_or(&0) + jokers;
+        Hand::hand_type(max_count, card_counts.len().max(1))
+    }
+
     fn get_all_possible_hands(&self) -> Vec<Self> {
         let card_counts = counts(self.cards.iter().cloned());
         let has_j = card_counts.contains_key(&1);
@@ -125,9 +147,9 @@ impl Hand {
 
             for i in 2..14 {
                 let mut hand = self.clone();
-                hand.cards[j_idx as usize] = i;
+                hand.cards[j_idx] = i;
                 hands.extend(hand.get_all_possible_hands());
-                // eprintln!("self.bid: {}, {:?}", self.bid, hands);
+                trace!("self.bid: {}, {:?}", self.bid, hands);
             }
 
             hands
@@ -185,53 +207,96 @@ impl Hand {
 pub struct Day7;
 
 impl Day7 {
-    fn parse(path: &str, val_func: fn(char) -> u32) -> Result<Vec<Hand>> {
-        let f = File::open(path)?;
-        let mut reader = BufReader::new(f);
-        let mut data = String::new();
+    fn parse_hands(input: &Input, val_func: fn(char) -> Option<u32>) -> Result<Vec<Hand>> {
+        let path = input.name();
         let mut hands = vec![];
-
-        loop {
-            let n = reader.read_line(&mut data)?;
-            if n == 0 {
-                break;
-            }
-            hands.push(Hand::new(data.clone(), val_func));
-            data.clear();
+        for (i, line) in Day7::input_lines(input)?.enumerate() {
+            let text = line?;
+            hands.push(Hand::new(&InputLine::new(path, i, &text), val_func)?);
         }
         Ok(hands)
     }
-}
 
-impl Solution for Day7 {
-    fn problem1(path: &str) -> std::io::Result<()> {
-        let mut hands = Day7::parse(path, get_value)?;
-        hands.sort_by(|a, b| a.compare(b));
-        let total = hands
+    fn joker_winnings(hands: &Hands, score: fn(&Hand) -> u32) -> u32 {
+        let mut hands = hands.joker_hands.clone();
+        hands
+            .iter_mut()
+            .for_each(|hand| hand.score = Some(score(hand)));
+        hands.sort_by(|a, b| a.compare2(b));
+        hands
             .iter()
             .enumerate()
             .map(|(i, h)| (i as u32 + 1) * h.bid)
-            .sum::<u32>();
+            .sum::<u32>()
+    }
 
-        println!("Got answer to day 7 problem 1: {}", total);
+    /// Part 2 with the jokers of every hand added to its most common card instead of
+    /// tried as every other card
+    pub fn part2_counted(input: &Input) -> Result<Answer> {
+        let hands = Day7::parse(input)?;
+        Ok(Day7::joker_winnings(&hands, Hand::compute_joker_score).into())
+    }
+}
+
+impl Solution for Day7 {
+    const DAY: u32 = 7;
+    const TITLE: &'static str = "Camel Cards";
 
-        Ok(())
+    type Parsed = Hands;
+
+    fn parse(input: &Input) -> Result<Hands> {
+        Ok(Hands {
+            hands: Day7::parse_hands(input, get_value)?,
+            joker_hands: Day7::parse_hands(input, get_value2)?,
+        })
     }
 
-    fn problem2(path: &str) -> std::io::Result<()> {
-        let mut hands = Day7::parse(path, get_value2)?;
-        hands
-            .iter_mut()
-            .for_each(|hand| hand.score = Some(hand.compute_score2()));
-        hands.sort_by(|a, b| a.compare2(b));
+    fn part1(_input: &Input, hands: &Hands) -> Result<Answer> {
+        let mut hands = hands.hands.clone();
+        hands.sort_by(|a, b| a.compare(b));
         let total = hands
             .iter()
             .enumerate()
             .map(|(i, h)| (i as u32 + 1) * h.bid)
             .sum::<u32>();
 
-        println!("Got answer to day 7 problem 2: {}", total);
+        Ok(total.into())
+    }
+
+    fn part2(_input: &Input, hands: &Hands) -> Result<Answer> {
+        Ok(Day7::joker_winnings(hands, Hand::compute_score2).into())
+    }
+
+    fn generate(rng: &mut Rng, size: usize) -> String {
+        let mut out = String::new();
+        for _ in 0..size {
+            out += &format!("{} {}\n", rng.string("23456789TJQKA", 5..=5), rng.range(1..=999));
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "\
+32T3K 765
+T55J5 684
+KK677 28
+KTJJT 220
+QQQJA 483
+";
+
+    #[test]
+    fn part1_example() {
+        let input = Input::text("example", EXAMPLE);
+        assert_eq!(Day7::problem1(&input).unwrap(), Answer::Int(6440));
+    }
 
-        Ok(())
+    #[test]
+    fn part2_example() {
+        let input = Input::text("example", EXAMPLE);
+        assert_eq!(Day7::problem2(&input).unwrap(), Answer::Int(5905));
     }
 }
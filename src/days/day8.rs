@@ -1,11 +1,12 @@
+use super::answer::Answer;
 use super::solution::Solution;
+use crate::error::{Error, InputLine, Result};
+use crate::generate::Rng;
+use crate::input::Input;
+use crate::input::Block;
 use lazy_static::lazy_static;
 use regex::Regex;
-use std::fs::File;
-use std::{
-    collections::HashMap,
-    io::{BufRead, BufReader, Result},
-};
+use std::collections::{HashMap, HashSet};
 
 lazy_static! {
     static ref NODE_RE: Regex =
@@ -15,27 +16,36 @@ lazy_static! {
 const TERM: &str = "ZZZ";
 
 #[derive(Clone, Debug)]
-enum Instruction {
+pub enum Instruction {
     Left,
     Right,
 }
 
 #[derive(Clone, Debug)]
-struct Node {
-    val: String,
-    left: String,
-    right: String,
+pub struct Node {
+    pub val: String,
+    pub left: String,
+    pub right: String,
 }
 
-fn parse_nodes(reader: &mut BufReader<File>) -> HashMap<String, Node> {
+/// The left/right instructions and the nodes they are followed through
+#[derive(Clone, Debug)]
+pub struct Network {
+    pub instructions: Vec<Instruction>,
+    pub nodes: HashMap<String, Node>,
+}
+
+fn parse_nodes(path: &str, block: &Block) -> Result<HashMap<String, Node>> {
     let mut nodes = HashMap::new();
 
-    for line in reader.lines().skip(1) {
-        let text = line.unwrap();
-        let node_match = NODE_RE.captures(&text).unwrap();
-        let val = node_match.name("val").unwrap().as_str().to_owned();
-        let left = node_match.name("left").unwrap().as_str().to_owned();
-        let right = node_match.name("right").unwrap().as_str().to_owned();
+    for (i, text) in block.numbered() {
+        let input_line = InputLine::new(path, i, text);
+        let Some(node_match) = NODE_RE.captures(text) else {
+            return Err(input_line.error("expected `<node> = (<left>, <right>)`"));
+        };
+        let val = node_match["val"].to_owned();
+        let left = node_match["left"].to_owned();
+        let right = node_match["right"].to_owned();
 
         let node = Node {
             val: val.clone(),
@@ -45,21 +55,40 @@ fn parse_nodes(reader: &mut BufReader<File>) -> HashMap<String, Node> {
         nodes.insert(val, node);
     }
 
-    nodes
+    Ok(nodes)
 }
 
-fn parse_instructions(reader: &mut BufReader<File>) -> Result<Vec<Instruction>> {
-    let mut raw = String::new();
-    reader.read_line(&mut raw)?;
-    Ok(raw
-        .chars()
-        .filter(|c| !c.is_whitespace())
-        .map(|c| match c {
-            'L' => Instruction::Left,
-            'R' => Instruction::Right,
-            _ => panic!(),
+fn parse_instructions(path: &str, block: &Block) -> Result<Vec<Instruction>> {
+    let mut lines = block.numbered();
+    let (i, raw) = lines.next().unwrap_or_default();
+    let line = InputLine::new(path, i, raw);
+    if let Some((i, text)) = lines.next() {
+        return Err(InputLine::new(path, i, text).error("expected a blank line after the instructions"));
+    }
+    line.text
+        .char_indices()
+        .filter(|(_, c)| !c.is_whitespace())
+        .map(|(i, c)| match c {
+            'L' => Ok(Instruction::Left),
+            'R' => Ok(Instruction::Right),
+            _ => Err(line.error_at(&line.text[i..i + c.len_utf8()], "expected `L` or `R`")),
         })
-        .collect())
+        .collect()
+}
+
+fn step<'a>(
+    path: &str,
+    nodes: &'a HashMap<String, Node>,
+    node: &Node,
+    inst: &Instruction,
+) -> Result<&'a Node> {
+    let next = match inst {
+        Instruction::Left => &node.left,
+        Instruction::Right => &node.right,
+    };
+    nodes
+        .get(next)
+        .ok_or_else(|| Error::no_solution(path, format!("node `{}` is not defined", next)))
 }
 
 fn gcd(mut i: u64, mut j: u64) -> u64 {
@@ -75,42 +104,69 @@ fn lcm(i: u64, j: u64) -> u64 {
     i * j / gcd(i, j)
 }
 
-fn lcms(n: Vec<u64>) -> u64 {
-    n.into_iter().reduce(lcm).unwrap()
+fn lcms(n: Vec<u64>) -> Option<u64> {
+    n.into_iter().reduce(lcm)
+}
+
+fn parse_network(input: &Input) -> Result<Network> {
+    let path = input.name();
+    let mut blocks = Day8::input_blocks(input)?;
+    let instructions = match blocks.next().transpose()? {
+        Some(block) => parse_instructions(path, &block)?,
+        None => vec![],
+    };
+    let mut nodes = HashMap::new();
+    for block in blocks {
+        nodes.extend(parse_nodes(path, &block?)?);
+    }
+
+    Ok(Network {
+        instructions,
+        nodes,
+    })
 }
 
 pub struct Day8;
 
 impl Solution for Day8 {
-    fn problem1(path: &str) -> std::io::Result<()> {
-        let file = File::open(path)?;
-        let mut reader = BufReader::new(file);
-        // return Ok(());
-        let instructions = parse_instructions(&mut reader)?;
-        let nodes = parse_nodes(&mut reader);
-
-        let mut current_node = nodes.get("AAA").unwrap();
+    const DAY: u32 = 8;
+    const TITLE: &'static str = "Haunted Wasteland";
+
+    type Parsed = Network;
+
+    fn parse(input: &Input) -> Result<Network> {
+        parse_network(input)
+    }
+
+    fn part1(input: &Input, network: &Network) -> Result<Answer> {
+        let path = input.name();
+        let Network {
+            instructions,
+            nodes,
+        } = network;
+
+        let mut current_node = nodes
+            .get("AAA")
+            .ok_or_else(|| Error::no_solution(path, "node `AAA` is not defined"))?;
+        let mut steps = 0;
         for (i, inst) in instructions.iter().cycle().enumerate() {
             if current_node.val == TERM {
-                println!("Got answer for Day8 Problem1: {}", i);
+                steps = i;
                 break;
             }
 
-            current_node = match inst {
-                Instruction::Left => nodes.get(&current_node.left).unwrap(),
-                Instruction::Right => nodes.get(&current_node.right).unwrap(),
-            }
+            current_node = step(path, nodes, current_node, inst)?;
         }
 
-        Ok(())
+        Ok(steps.into())
     }
 
-    fn problem2(path: &str) -> std::io::Result<()> {
-        let file = File::open(path)?;
-        let mut reader = BufReader::new(file);
-
-        let instructions = parse_instructions(&mut reader)?;
-        let nodes = parse_nodes(&mut reader);
+    fn part2(input: &Input, network: &Network) -> Result<Answer> {
+        let path = input.name();
+        let Network {
+            instructions,
+            nodes,
+        } = network;
 
         let mut current_nodes: Vec<&Node> =
             nodes.values().filter(|n| n.val.ends_with('A')).collect();
@@ -124,14 +180,97 @@ impl Solution for Day8 {
                     break;
                 }
 
-                *current_node = match inst {
-                    Instruction::Left => nodes.get(&current_node.left).unwrap(),
-                    Instruction::Right => nodes.get(&current_node.right).unwrap(),
-                }
+                *current_node = step(path, nodes, current_node, inst)?;
             }
         }
-        println!("Got answer for problem2 day 8, {}", lcms(multiples));
+        let steps = lcms(multiples)
+            .ok_or_else(|| Error::no_solution(path, "no nodes end with `A`"))?;
+        Ok(steps.into())
+    }
+
+    fn generate(rng: &mut Rng, size: usize) -> String {
+        const LETTERS: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+        const MIDDLE: &str = "BCDEFGHIJKLMNOPQRSTUVWXY";
+
+        let mut names = HashSet::from(["AAA".to_owned(), TERM.to_owned()]);
+        let mut unique = |rng: &mut Rng, last: &str| loop {
+            let name = rng.string(LETTERS, 2..=2) + &rng.string(last, 1..=1);
+            if names.insert(name.clone()) {
+                break name;
+            }
+        };
+
+        // Each ghost walks its own chain from a `..A` node to a `..Z` node, and the `..Z`
+        // node leads back into the chain so the distance to it repeats, which part 2
+        // relies on. Both turns of a node lead the same way so every instruction works.
+        let mut nodes = vec![];
+        for ghost in 0..size.div_ceil(10).min(5) {
+            let (start, end) = if ghost == 0 {
+                ("AAA".to_owned(), TERM.to_owned())
+            } else {
+                (unique(rng, "A"), unique(rng, "Z"))
+            };
+
+            let mut chain = vec![start];
+            chain.extend((1..rng.usize(1..=size)).map(|_| unique(rng, MIDDLE)));
+            chain.push(end);
+            for pair in chain.windows(2) {
+                nodes.push((pair[0].clone(), pair[1].clone()));
+            }
+            nodes.push((chain[chain.len() - 1].clone(), chain[1].clone()));
+        }
+        for _ in 0..size {
+            let node = unique(rng, MIDDLE);
+            nodes.push((node.clone(), node));
+        }
+        rng.shuffle(&mut nodes);
+
+        let mut out = rng.string("LR", 1..=size) + "\n\n";
+        for (node, next) in nodes {
+            out += &format!("{} = ({}, {})\n", node, next, next);
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "\
+RL
+
+AAA = (BBB, CCC)
+BBB = (DDD, EEE)
+CCC = (ZZZ, GGG)
+DDD = (DDD, DDD)
+EEE = (EEE, EEE)
+GGG = (GGG, GGG)
+ZZZ = (ZZZ, ZZZ)
+";
+
+    const GHOSTS: &str = "\
+LR
+
+11A = (11B, XXX)
+11B = (XXX, 11Z)
+11Z = (11B, XXX)
+22A = (22B, XXX)
+22B = (22C, 22C)
+22C = (22Z, 22Z)
+22Z = (22B, 22B)
+XXX = (XXX, XXX)
+";
+
+    #[test]
+    fn part1_example() {
+        let input = Input::text("example", EXAMPLE);
+        assert_eq!(Day8::problem1(&input).unwrap(), Answer::Int(2));
+    }
 
-        Ok(())
+    #[test]
+    fn part2_ghosts() {
+        let input = Input::text("example", GHOSTS);
+        assert_eq!(Day8::problem2(&input).unwrap(), Answer::Int(6));
     }
 }
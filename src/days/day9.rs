@@ -1,23 +1,24 @@
+use super::answer::Answer;
 use super::solution::Solution;
 use std::collections::VecDeque;
-use std::fs::File;
-use std::io::prelude::*;
-use std::io::{BufReader, Result};
+use crate::error::{InputLine, Result};
+use crate::generate::Rng;
+use crate::input::Input;
 
 pub struct Day9;
 
 impl Day9 {
-    fn parse(path: &str) -> Result<Vec<Vec<i32>>> {
-        let file = File::open(path)?;
-        let reader = BufReader::new(file);
+    fn parse_sequences(input: &Input) -> Result<Vec<Vec<i32>>> {
+        let path = input.name();
         let mut sequences = vec![];
 
-        for line in reader.lines() {
+        for (i, line) in Day9::input_lines(input)?.enumerate() {
             let text = line?;
-            let sequence: Vec<i32> = text
+            let input_line = InputLine::new(path, i, &text);
+            let sequence = text
                 .split_whitespace()
-                .map(|n: &str| n.parse::<i32>().unwrap())
-                .collect();
+                .map(|n: &str| input_line.number::<i32>(n))
+                .collect::<Result<Vec<i32>>>()?;
             sequences.push(sequence);
         }
 
@@ -52,20 +53,29 @@ impl Day9 {
 }
 
 impl Solution for Day9 {
-    fn problem1(path: &str) -> Result<()> {
-        let sequences = Day9::parse(path)?;
+    const DAY: u32 = 9;
+    const TITLE: &'static str = "Mirage Maintenance";
+
+    type Parsed = Vec<Vec<i32>>;
+
+    fn parse(input: &Input) -> Result<Vec<Vec<i32>>> {
+        Day9::parse_sequences(input)
+    }
+
+    fn part1(_input: &Input, sequences: &Vec<Vec<i32>>) -> Result<Answer> {
         let total = sequences
-            .into_iter()
+            .iter()
+            .cloned()
             .map(Day9::extend_sequence)
             .map(|s: Vec<i32>| *s.last().unwrap())
             .sum::<i32>();
-        println!("Got answer to Day 9 Problem 1: {}", total);
-        Ok(())
+        Ok(total.into())
     }
 
-    fn problem2(path: &str) -> Result<()> {
-        let sequences: Vec<VecDeque<i32>> = Day9::parse(path)?
-            .into_iter()
+    fn part2(_input: &Input, sequences: &Vec<Vec<i32>>) -> Result<Answer> {
+        let sequences: Vec<VecDeque<i32>> = sequences
+            .iter()
+            .cloned()
             .map(VecDeque::from_iter)
             .collect();
         let total = sequences
@@ -73,7 +83,43 @@ impl Solution for Day9 {
             .map(Day9::prepend_sequence)
             .map(|s: VecDeque<i32>| *s.front().unwrap())
             .sum::<i32>();
-        println!("Got answer to Day 9 Problem 2: {}", total);
-        Ok(())
+        Ok(total.into())
+    }
+
+    fn generate(rng: &mut Rng, size: usize) -> String {
+        let mut out = String::new();
+        for _ in 0..size {
+            // a polynomial of low degree so that the differences run out to zeros
+            let coefficients: Vec<i64> = (0..rng.usize(1..=4)).map(|_| rng.range(0..=10) as i64 - 5).collect();
+            let values: Vec<String> = (0..rng.range(6..=12) as i64)
+                .map(|n| coefficients.iter().rev().fold(0, |acc, c| acc * n + c).to_string())
+                .collect();
+            out += &values.join(" ");
+            out.push('\n');
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "\
+0 3 6 9 12 15
+1 3 6 10 15 21
+10 13 16 21 30 45
+";
+
+    #[test]
+    fn part1_example() {
+        let input = Input::text("example", EXAMPLE);
+        assert_eq!(Day9::problem1(&input).unwrap(), Answer::Int(114));
+    }
+
+    #[test]
+    fn part2_example() {
+        let input = Input::text("example", EXAMPLE);
+        assert_eq!(Day9::problem2(&input).unwrap(), Answer::Int(2));
     }
 }
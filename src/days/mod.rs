@@ -1,3 +1,4 @@
+pub mod answer;
 pub mod day1;
 pub mod day10;
 pub mod day11;
@@ -14,4 +15,5 @@ pub mod day6;
 pub mod day7;
 pub mod day8;
 pub mod day9;
+pub mod registry;
 pub mod solution;
@@ -0,0 +1,106 @@
+use super::answer::Answer;
+use super::solution::Solution;
+use super::{
+    day1::Day1, day10::Day10, day11::Day11, day12::Day12, day13::Day13, day14::Day14,
+    day15::Day15, day16::Day16, day2::Day2, day3::Day3, day4::Day4, day5::Day5, day6::Day6,
+    day7::Day7, day8::Day8, day9::Day9,
+};
+use crate::error::Result;
+use crate::generate::Rng;
+use crate::input::Input;
+use std::any::Any;
+
+pub type Part = fn(&Input) -> Result<Answer>;
+
+/// The parsed model of an input. Each day parses into its own type, so it's boxed
+/// up for the registry.
+pub type Parsed = Box<dyn Any>;
+
+pub type Parse = fn(&Input) -> Result<Parsed>;
+
+/// Solve a part from what `parse` of the same day returned
+pub type Solve = fn(&Input, &dyn Any) -> Result<Answer>;
+
+/// Build a random input of roughly the given size
+pub type Generate = fn(&mut Rng, usize) -> String;
+
+/// A registered day along with the functions that solve each of its parts
+pub struct Day {
+    pub number: u32,
+    pub title: &'static str,
+    pub parts: [Part; 2],
+    /// The steps of each part, for when parsing and solving are run separately
+    pub parse: Parse,
+    pub solve: [Solve; 2],
+    pub generate: Generate,
+}
+
+fn parse<S: Solution>(input: &Input) -> Result<Parsed>
+where
+    S::Parsed: 'static,
+{
+    Ok(Box::new(S::parse(input)?))
+}
+
+fn solve<S: Solution, const PART: usize>(input: &Input, parsed: &dyn Any) -> Result<Answer>
+where
+    S::Parsed: 'static,
+{
+    let parsed = parsed
+        .downcast_ref::<S::Parsed>()
+        .expect("solved from the input parsed by another day");
+    match PART {
+        1 => S::part1(input, parsed),
+        _ => S::part2(input, parsed),
+    }
+}
+
+impl Day {
+    const fn of<S: Solution>() -> Self
+    where
+        S::Parsed: 'static,
+    {
+        Self {
+            number: S::DAY,
+            title: S::TITLE,
+            parts: [S::problem1, S::problem2],
+            parse: parse::<S>,
+            solve: [solve::<S, 1>, solve::<S, 2>],
+            generate: S::generate,
+        }
+    }
+}
+
+macro_rules! registry {
+    ($($day:ty),+ $(,)?) => {
+        pub const DAYS: &[Day] = &[$(Day::of::<$day>()),+];
+    };
+}
+
+registry![
+    Day1, Day2, Day3, Day4, Day5, Day6, Day7, Day8, Day9, Day10, Day11, Day12, Day13, Day14,
+    Day15, Day16,
+];
+
+/// How many `dayN` modules there are. Bump it along with adding a module, and the
+/// build fails until the new day is registered above.
+const DAY_MODULES: usize = 16;
+
+// Every day has to be registered in order without gaps so that a day can be looked
+// up by its index. Registering days out of order or leaving one out fails the build
+// here, and so does forgetting to register the newest day once `DAY_MODULES` counts it.
+const _: () = {
+    assert!(DAYS.len() == DAY_MODULES, "every day module has to be registered");
+    let mut i = 0;
+    while i < DAYS.len() {
+        assert!(
+            DAYS[i].number as usize == i + 1,
+            "days must be registered in order without gaps"
+        );
+        i += 1;
+    }
+};
+
+pub fn get(number: u32) -> Option<&'static Day> {
+    DAYS.get((number as usize).checked_sub(1)?)
+}
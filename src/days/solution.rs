@@ -1,17 +1,24 @@
-use std::fs::File;
-use std::io::{BufRead, BufReader, Result};
+use super::answer::Answer;
+use crate::error::{Error, InputLine, Result, WithPath};
+use crate::generate::Rng;
+use crate::grid::{Grid, Point};
+use crate::input::{Blocks, Input, Lines};
+use std::io::BufRead;
 
-pub(crate) trait Solution {
-    fn read_input_into_lines(path: &str) -> Result<Vec<String>> {
-        let file = File::open(path)?;
-        let mut reader = BufReader::new(file);
+pub trait Solution {
+    const DAY: u32;
+    const TITLE: &'static str;
+
+    /// Every line of the input with its line ending kept
+    fn read_input_into_lines(input: &Input) -> Result<Vec<String>> {
+        let mut reader = input.open()?;
         let mut lines = Vec::new();
 
         loop {
             let mut line = String::new();
-            match reader.read_line(&mut line) {
+            match reader.read_line(&mut line).with_path(input.name()) {
                 Ok(n) if n > 0 => lines.push(line),
-                Err(e) => return Result::Err(e),
+                Err(e) => return Err(e),
                 _ => break,
             };
         }
@@ -19,24 +26,62 @@ pub(crate) trait Solution {
         Ok(lines)
     }
 
-    fn read_input_into_grid(path: &str) -> Result<Vec<Vec<char>>> {
-        let file = File::open(path)?;
-        let mut reader = BufReader::new(file);
-        let mut grid = Vec::new();
+    /// A lazy iterator over the lines of the input without their line endings
+    fn input_lines(input: &Input) -> Result<Lines<'_>> {
+        input.lines()
+    }
 
-        let mut buf = String::new();
-        while let Ok(n) = reader.read_line(&mut buf) {
-            if n == 0 {
-                break;
-            }
+    /// A lazy iterator over the blocks of lines in the input that are separated by
+    /// blank lines
+    fn input_blocks(input: &Input) -> Result<Blocks<'_>> {
+        input.blocks()
+    }
+
+    fn read_input_to_string(input: &Input) -> Result<String> {
+        input.read_to_string()
+    }
 
-            grid.push(buf.trim().chars().collect());
-            buf.clear();
+    fn read_input_into_grid(input: &Input) -> Result<Grid<char>> {
+        let mut rows = Vec::new();
+        let mut lines = Vec::new();
+
+        for line in Self::input_lines(input)? {
+            let line = line?;
+            rows.push(line.trim().chars().collect());
+            lines.push(line);
         }
 
-        Ok(grid)
+        Grid::from_rows(rows).map_err(|i| {
+            InputLine::new(input.name(), i, &lines[i]).error("row length differs from the first row")
+        })
     }
 
-    fn problem1(path: &str) -> Result<()>;
-    fn problem2(path: &str) -> Result<()>;
+    /// Check that every tile of a grid is one of `tiles`
+    fn validate_grid(input: &Input, grid: &Grid<char>, tiles: &str) -> Result<()> {
+        match grid.iter().find(|(_, c)| !tiles.contains(**c)) {
+            Some((Point { x, y }, c)) => Err(Error::parse(input.name(), y + 1, x + 1, *c, "unknown tile")),
+            None => Ok(()),
+        }
+    }
+
+    /// The model of the puzzle input that both parts are solved from
+    type Parsed;
+
+    fn parse(input: &Input) -> Result<Self::Parsed>;
+
+    // `input` is only passed to the parts so that they can report errors against it
+    fn part1(input: &Input, parsed: &Self::Parsed) -> Result<Answer>;
+    fn part2(input: &Input, parsed: &Self::Parsed) -> Result<Answer>;
+
+    /// A random input in the puzzle's format that both parts can solve. `size` is
+    /// roughly the number of records in it, or the side of the grid for grid days.
+    fn generate(rng: &mut Rng, size: usize) -> String;
+
+    fn problem1(input: &Input) -> Result<Answer> {
+        Self::part1(input, &Self::parse(input)?)
+    }
+
+    fn problem2(input: &Input) -> Result<Answer> {
+        Self::part2(input, &Self::parse(input)?)
+    }
 }
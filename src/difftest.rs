@@ -0,0 +1,264 @@
+use crate::days::registry::{self, Part};
+use crate::days::{day12::Day12, day5::Day5, day7::Day7};
+use crate::generate::Rng;
+use crate::input::Input;
+use crate::runner;
+use lazy_static::lazy_static;
+use regex::Regex;
+
+lazy_static! {
+    static ref NUMBER_RE: Regex = Regex::new(r"\d+").unwrap();
+}
+
+/// A second solver for a part that its registered solver can be compared with.
+/// Usually that's a slow brute force, but day 7 solves part 2 by trying every card
+/// for each joker, so its reference is the rule that jokers join the most common card.
+pub struct Check {
+    pub day: u32,
+    pub part: usize,
+    /// What the reference is built on, for the report
+    pub name: &'static str,
+    /// Fails on inputs it can't solve, which are then left out of the comparison
+    pub reference: Part,
+}
+
+pub const CHECKS: &[Check] = &[
+    Check {
+        day: 5,
+        part: 2,
+        name: "p2_brute",
+        reference: Day5::part2_brute,
+    },
+    Check {
+        day: 7,
+        part: 2,
+        name: "compute_joker_score",
+        reference: Day7::part2_counted,
+    },
+    Check {
+        day: 12,
+        part: 1,
+        name: "get_all_combinations",
+        reference: Day12::part1_brute,
+    },
+];
+
+/// An input the fast solver and the reference gave different answers on
+#[derive(Debug)]
+pub struct Disagreement {
+    pub input: String,
+    pub fast: String,
+    pub reference: String,
+}
+
+/// Every input one step smaller than `text`: first with runs of lines removed,
+/// halving the run down to single lines, then with a number made smaller, then with
+/// a single character removed
+fn candidates(text: &str) -> impl Iterator<Item = String> + '_ {
+    let line_count = text.lines().count();
+    let sizes: Vec<usize> = std::iter::successors(Some(line_count / 2), |n| Some(n / 2))
+        .take_while(|n| *n > 0)
+        .collect();
+
+    let without_lines = sizes.into_iter().flat_map(move |size| {
+        (0..line_count).step_by(size).map(move |start| {
+            text.lines()
+                .enumerate()
+                .filter(|(i, _)| !(start..start + size).contains(i))
+                .map(|(_, line)| format!("{}\n", line))
+                .collect()
+        })
+    });
+
+    let smaller_numbers = NUMBER_RE.find_iter(text).flat_map(move |m| {
+        let n = m.as_str().parse::<u64>().unwrap_or(0);
+        let mut smaller = vec![0, 1, n / 2, n.saturating_sub(1)];
+        smaller.retain(|s| *s < n);
+        smaller.dedup();
+        smaller
+            .into_iter()
+            .map(move |s| format!("{}{}{}", &text[..m.start()], s, &text[m.end()..]))
+    });
+
+    let without_chars = text
+        .char_indices()
+        .filter(|(_, c)| *c != '\n')
+        .map(move |(i, c)| format!("{}{}", &text[..i], &text[i + c.len_utf8()..]));
+
+    without_lines.chain(smaller_numbers).chain(without_chars)
+}
+
+impl Check {
+    pub fn fast(&self) -> Part {
+        registry::get(self.day).expect("checks are for registered days").parts[self.part - 1]
+    }
+
+    /// Run both solvers on `text`. Gives nothing when they agree, and fails when the
+    /// reference can't solve it.
+    pub fn compare(&self, text: &str) -> Result<Option<Disagreement>, String> {
+        let input = Input::text("difftest", text);
+        let reference = runner::catch(|| (self.reference)(&input))?.to_string();
+        let fast = match runner::catch(|| self.fast()(&input)) {
+            Ok(answer) => answer.to_string(),
+            Err(e) => format!("error: {}", e),
+        };
+
+        Ok((fast != reference).then(|| Disagreement {
+            input: text.to_owned(),
+            fast,
+            reference,
+        }))
+    }
+
+    /// Whether the solvers disagree on `text`, counting it as agreeing when the
+    /// reference can't solve it. Only for shrinking, where every input is one step
+    /// from an input that was compared.
+    fn disagrees(&self, text: &str) -> Option<Disagreement> {
+        self.compare(text).ok().flatten()
+    }
+
+    /// Make a disagreement as small as it gets by taking one step smaller at a time
+    /// for as long as the solvers still disagree
+    pub fn shrink(&self, mut disagreement: Disagreement) -> Disagreement {
+        loop {
+            let smaller = candidates(&disagreement.input).find_map(|text| self.disagrees(&text));
+            match smaller {
+                Some(smaller) => disagreement = smaller,
+                None => return disagreement,
+            }
+        }
+    }
+
+    /// Compare the solvers on `runs` generated inputs, the nth of which is made
+    /// from `seed + n`. Returns the seed of the first disagreement along with it, and
+    /// fails when the reference couldn't solve any of the inputs.
+    pub fn generated(&self, seed: u64, runs: usize, size: usize) -> Result<Option<(u64, Disagreement)>, String> {
+        let generate = registry::get(self.day).expect("checks are for registered days").generate;
+        let mut compared = 0;
+        for n in 0..runs as u64 {
+            let seed = seed.wrapping_add(n);
+            let text = generate(&mut Rng::new(seed), size);
+            match self.compare(&text) {
+                Ok(Some(disagreement)) => return Ok(Some((seed, disagreement))),
+                Ok(None) => compared += 1,
+                Err(_) => continue,
+            }
+        }
+
+        if compared == 0 {
+            return Err(format!("the reference solved none of the {} generated inputs", runs));
+        }
+        Ok(None)
+    }
+}
+
+pub fn checks(day: u32) -> impl Iterator<Item = &'static Check> {
+    CHECKS.iter().filter(move |check| check.day == day)
+}
+
+fn print_disagreement(check: &Check, disagreement: &Disagreement) {
+    println!("    fast:      {}", disagreement.fast);
+    println!("    {}: {}", check.name, disagreement.reference);
+    println!("    shrunk input:");
+    for line in disagreement.input.lines() {
+        println!("        {}", line);
+    }
+}
+
+/// Compare every check of the days in `days`, either on `input` or on generated
+/// inputs, printing a line per check and the shrunk input of every disagreement.
+/// Returns whether every check agreed.
+pub fn difftest(
+    days: impl Iterator<Item = u32>,
+    input: Option<&Input>,
+    seed: u64,
+    runs: usize,
+    size: usize,
+) -> bool {
+    let mut agreed = true;
+
+    for check in days.flat_map(checks) {
+        let label = format!("day {} part {} against {}", check.day, check.part, check.name);
+        let found = match input {
+            Some(input) => {
+                let text = match input.read_to_string() {
+                    Ok(text) => text,
+                    Err(e) => {
+                        println!("FAIL {}: {}", label, e);
+                        agreed = false;
+                        continue;
+                    }
+                };
+                match check.compare(&text) {
+                    Ok(found) => found.map(|d| (input.name().to_owned(), d)),
+                    Err(e) => {
+                        println!("SKIP {}: {}", label, e);
+                        continue;
+                    }
+                }
+            }
+            None => match check.generated(seed, runs, size) {
+                Ok(found) => found.map(|(seed, d)| (format!("seed {}", seed), d)),
+                Err(e) => {
+                    println!("SKIP {}: {}", label, e);
+                    continue;
+                }
+            },
+        };
+
+        match found {
+            None => println!("AGREE {}", label),
+            Some((source, disagreement)) => {
+                println!("DIFFER {} on {}", label, source);
+                print_disagreement(check, &check.shrink(disagreement));
+                agreed = false;
+            }
+        }
+    }
+
+    agreed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::days::answer::Answer;
+    use crate::error::Error;
+
+    fn never_solves(input: &Input) -> crate::error::Result<Answer> {
+        Err(Error::no_solution(input.name(), "never solves anything"))
+    }
+
+    const UNSOLVABLE: Check = Check {
+        day: 7,
+        part: 2,
+        name: "never_solves",
+        reference: never_solves,
+    };
+
+    #[test]
+    fn generated_inputs_the_reference_cannot_solve_are_not_an_agreement() {
+        let err = UNSOLVABLE.generated(1, 5, 5).unwrap_err();
+        assert_eq!(err, "the reference solved none of the 5 generated inputs");
+    }
+
+    #[test]
+    fn supplied_input_the_reference_cannot_solve_is_an_error() {
+        assert!(UNSOLVABLE.compare("32T3K 765\n").is_err());
+    }
+
+    #[test]
+    fn checks_agree_on_generated_inputs() {
+        for check in checks(7) {
+            assert!(matches!(check.generated(1, 10, 5), Ok(None)));
+        }
+    }
+
+    #[test]
+    fn shrinks_lines_and_numbers() {
+        let smaller: Vec<String> = candidates("ab 12\ncd 3\n").collect();
+        assert!(smaller.contains(&"cd 3\n".to_owned()));
+        assert!(smaller.contains(&"ab 6\ncd 3\n".to_owned()));
+        assert!(smaller.contains(&"b 12\ncd 3\n".to_owned()));
+    }
+}
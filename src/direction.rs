@@ -0,0 +1,78 @@
+/// A compass direction on a [`Grid`](crate::grid::Grid) where north is towards the
+/// first row and east is towards the last column
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Direction {
+    North,
+    East,
+    South,
+    West,
+}
+
+impl Direction {
+    /// Every direction, clockwise starting from north
+    pub const ALL: [Direction; 4] = [
+        Direction::North,
+        Direction::East,
+        Direction::South,
+        Direction::West,
+    ];
+
+    pub fn iter() -> impl Iterator<Item = Direction> {
+        Direction::ALL.into_iter()
+    }
+
+    /// A quarter turn counter clockwise
+    pub fn turn_left(self) -> Self {
+        match self {
+            Direction::North => Direction::West,
+            Direction::East => Direction::North,
+            Direction::South => Direction::East,
+            Direction::West => Direction::South,
+        }
+    }
+
+    /// A quarter turn clockwise
+    pub fn turn_right(self) -> Self {
+        match self {
+            Direction::North => Direction::East,
+            Direction::East => Direction::South,
+            Direction::South => Direction::West,
+            Direction::West => Direction::North,
+        }
+    }
+
+    pub fn opposite(self) -> Self {
+        match self {
+            Direction::North => Direction::South,
+            Direction::East => Direction::West,
+            Direction::South => Direction::North,
+            Direction::West => Direction::East,
+        }
+    }
+
+    /// The direction of travel after bouncing off a `/` mirror
+    pub fn reflect_slash(self) -> Self {
+        match self {
+            Direction::North | Direction::South => self.turn_right(),
+            Direction::East | Direction::West => self.turn_left(),
+        }
+    }
+
+    /// The direction of travel after bouncing off a `\` mirror
+    pub fn reflect_backslash(self) -> Self {
+        match self {
+            Direction::North | Direction::South => self.turn_left(),
+            Direction::East | Direction::West => self.turn_right(),
+        }
+    }
+
+    /// The change in `(x, y)` of a single step in this direction
+    pub fn delta(self) -> (isize, isize) {
+        match self {
+            Direction::North => (0, -1),
+            Direction::East => (1, 0),
+            Direction::South => (0, 1),
+            Direction::West => (-1, 0),
+        }
+    }
+}
@@ -0,0 +1,149 @@
+use std::fmt::{self, Display};
+use std::io;
+use std::str::FromStr;
+
+/// Everything that can go wrong while reading or solving a puzzle input
+#[derive(Debug)]
+pub enum Error {
+    /// The input could not be read
+    Io { path: String, source: io::Error },
+    /// A line of the input did not have the expected format. `line` and `column`
+    /// are 1-based and `text` is the offending part of the line.
+    Parse {
+        path: String,
+        line: usize,
+        column: usize,
+        text: String,
+        message: String,
+    },
+    /// The input was well formed but has no answer
+    NoSolution { path: String, message: String },
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl Error {
+    pub fn io(path: &str, source: io::Error) -> Self {
+        Error::Io {
+            path: path.to_owned(),
+            source,
+        }
+    }
+
+    pub fn parse(
+        path: &str,
+        line: usize,
+        column: usize,
+        text: impl Into<String>,
+        message: impl Into<String>,
+    ) -> Self {
+        Error::Parse {
+            path: path.to_owned(),
+            line,
+            column,
+            text: text.into(),
+            message: message.into(),
+        }
+    }
+
+    pub fn no_solution(path: &str, message: impl Into<String>) -> Self {
+        Error::NoSolution {
+            path: path.to_owned(),
+            message: message.into(),
+        }
+    }
+}
+
+impl Error {
+    /// What went wrong, without where
+    pub fn message(&self) -> String {
+        match self {
+            Error::Io { source, .. } => source.to_string(),
+            Error::Parse { message, .. } | Error::NoSolution { message, .. } => message.clone(),
+        }
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io { path, source } => write!(f, "{}: {}", path, source),
+            Error::Parse {
+                path,
+                line,
+                column,
+                text,
+                message,
+            } => write!(f, "{}:{}:{}: {}: `{}`", path, line, column, message, text),
+            Error::NoSolution { path, message } => write!(f, "{}: {}", path, message),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+/// Attach the path of the input being read to an io error
+pub trait WithPath<T> {
+    fn with_path(self, path: &str) -> Result<T>;
+}
+
+impl<T> WithPath<T> for io::Result<T> {
+    fn with_path(self, path: &str) -> Result<T> {
+        self.map_err(|e| Error::io(path, e))
+    }
+}
+
+/// A single line of an input file, used to point parse errors at the offending text
+#[derive(Debug, Clone, Copy)]
+pub struct InputLine<'a> {
+    pub path: &'a str,
+    /// 1-based line number
+    pub number: usize,
+    pub text: &'a str,
+}
+
+impl<'a> InputLine<'a> {
+    /// `index` is the 0-based line index as produced by `enumerate`
+    pub fn new(path: &'a str, index: usize, text: &'a str) -> Self {
+        Self {
+            path,
+            number: index + 1,
+            text: text.trim_end_matches(['\n', '\r']),
+        }
+    }
+
+    /// The 1-based column at which `part` starts when it is a slice of this line
+    fn column_of(&self, part: &str) -> usize {
+        let start = self.text.as_ptr() as usize;
+        let offset = (part.as_ptr() as usize).wrapping_sub(start);
+        if offset <= self.text.len() && self.text.is_char_boundary(offset) {
+            self.text[..offset].chars().count() + 1
+        } else {
+            1
+        }
+    }
+
+    /// An error pointing at `part`, which should be a slice of this line
+    pub fn error_at(&self, part: &str, message: impl Into<String>) -> Error {
+        Error::parse(self.path, self.number, self.column_of(part), part, message)
+    }
+
+    /// An error pointing at the whole line
+    pub fn error(&self, message: impl Into<String>) -> Error {
+        self.error_at(self.text, message)
+    }
+
+    /// Parse `part`, which should be a slice of this line, as a number
+    pub fn number<T: FromStr>(&self, part: &str) -> Result<T> {
+        part.trim()
+            .parse::<T>()
+            .map_err(|_| self.error_at(part, "expected a number"))
+    }
+}
@@ -0,0 +1,72 @@
+use std::ops::RangeInclusive;
+
+/// A small seeded random number generator (splitmix64) so that a generated input
+/// can be reproduced from its seed
+#[derive(Debug, Clone)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+
+    /// A number in `0..n`
+    pub fn below(&mut self, n: u64) -> u64 {
+        assert!(n > 0, "below needs a non-empty range");
+        self.next_u64() % n
+    }
+
+    pub fn range(&mut self, range: RangeInclusive<u64>) -> u64 {
+        range.start() + self.below(range.end() - range.start() + 1)
+    }
+
+    /// Like [`range`](Rng::range) but for lengths and indices
+    pub fn usize(&mut self, range: RangeInclusive<usize>) -> usize {
+        self.range(*range.start() as u64..=*range.end() as u64) as usize
+    }
+
+    /// True with probability `p`
+    pub fn chance(&mut self, p: f64) -> bool {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64 <= p
+    }
+
+    pub fn pick<'a, T>(&mut self, items: &'a [T]) -> &'a T {
+        &items[self.usize(0..=items.len() - 1)]
+    }
+
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            items.swap(i, self.usize(0..=i));
+        }
+    }
+
+    /// A string of characters picked from `chars` with a length in `len`
+    pub fn string(&mut self, chars: &str, len: RangeInclusive<usize>) -> String {
+        let chars: Vec<char> = chars.chars().collect();
+        let len = self.usize(len);
+        (0..len).map(|_| *self.pick(&chars)).collect()
+    }
+}
+
+/// A grid of `width` by `height` tiles where each tile is picked by `tile`, one line
+/// per row
+pub fn grid(rng: &mut Rng, width: usize, height: usize, mut tile: impl FnMut(&mut Rng) -> char) -> String {
+    let mut out = String::new();
+    for _ in 0..height {
+        for _ in 0..width {
+            out.push(tile(rng));
+        }
+        out.push('\n');
+    }
+    out
+}
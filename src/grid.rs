@@ -0,0 +1,292 @@
+use crate::direction::Direction;
+use std::fmt::{self, Display};
+use std::ops::{Index, IndexMut};
+
+/// A position in a [`Grid`]. `x` is the column and `y` is the row, counting down
+/// from the top left corner.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Point {
+    pub x: usize,
+    pub y: usize,
+}
+
+impl Point {
+    pub const fn new(x: usize, y: usize) -> Self {
+        Self { x, y }
+    }
+
+    pub fn manhattan_distance(&self, other: &Point) -> usize {
+        self.x.abs_diff(other.x) + self.y.abs_diff(other.y)
+    }
+}
+
+const NEIGHBORS_8: [(isize, isize); 8] = [
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+    (1, 0),
+    (1, 1),
+    (0, 1),
+    (-1, 1),
+    (-1, 0),
+];
+
+/// A dense rectangular grid stored in row-major order
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Grid<T> {
+    width: usize,
+    height: usize,
+    cells: Vec<T>,
+}
+
+impl<T> Grid<T> {
+    /// Build a grid from its cells in row-major order. Panics if the number of cells
+    /// doesn't match the dimensions.
+    pub fn new(width: usize, height: usize, cells: Vec<T>) -> Self {
+        assert_eq!(width * height, cells.len(), "grid cells don't match its size");
+        Self {
+            width,
+            height,
+            cells,
+        }
+    }
+
+    /// Build a grid from its rows. Returns the index of the first row whose length
+    /// differs from the first one if the rows are ragged.
+    pub fn from_rows(rows: Vec<Vec<T>>) -> Result<Self, usize> {
+        let width = rows.first().map_or(0, Vec::len);
+        if let Some(i) = rows.iter().position(|row| row.len() != width) {
+            return Err(i);
+        }
+
+        let height = rows.len();
+        let cells = rows.into_iter().flatten().collect();
+        Ok(Self::new(width, height, cells))
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn contains(&self, p: Point) -> bool {
+        p.x < self.width && p.y < self.height
+    }
+
+    pub fn get(&self, p: Point) -> Option<&T> {
+        if self.contains(p) {
+            Some(&self.cells[p.y * self.width + p.x])
+        } else {
+            None
+        }
+    }
+
+    pub fn get_mut(&mut self, p: Point) -> Option<&mut T> {
+        if self.contains(p) {
+            Some(&mut self.cells[p.y * self.width + p.x])
+        } else {
+            None
+        }
+    }
+
+    /// The point `dx` columns and `dy` rows away from `p` if it is inside the grid
+    pub fn offset(&self, p: Point, dx: isize, dy: isize) -> Option<Point> {
+        let x = p.x.checked_add_signed(dx)?;
+        let y = p.y.checked_add_signed(dy)?;
+        let next = Point::new(x, y);
+        self.contains(next).then_some(next)
+    }
+
+    /// The point one step from `p` in `direction` if it is inside the grid
+    pub fn step(&self, p: Point, direction: Direction) -> Option<Point> {
+        let (dx, dy) = direction.delta();
+        self.offset(p, dx, dy)
+    }
+
+    /// The points directly above, right of, below and left of `p` that are inside the grid
+    pub fn neighbors4(&self, p: Point) -> impl Iterator<Item = Point> + '_ {
+        Direction::iter().filter_map(move |direction| self.step(p, direction))
+    }
+
+    /// The points surrounding `p`, including diagonals, that are inside the grid
+    pub fn neighbors8(&self, p: Point) -> impl Iterator<Item = Point> + '_ {
+        NEIGHBORS_8
+            .iter()
+            .filter_map(move |(dx, dy)| self.offset(p, *dx, *dy))
+    }
+
+    /// Every point in the grid in row-major order
+    pub fn points(&self) -> impl Iterator<Item = Point> {
+        let width = self.width;
+        (0..self.height).flat_map(move |y| (0..width).map(move |x| Point::new(x, y)))
+    }
+
+    /// Every cell in the grid along with its position in row-major order
+    pub fn iter(&self) -> impl Iterator<Item = (Point, &T)> {
+        self.points().zip(self.cells.iter())
+    }
+
+    pub fn row(&self, y: usize) -> &[T] {
+        &self.cells[y * self.width..(y + 1) * self.width]
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> {
+        // chunks panics on a zero size so an empty grid is given a size that
+        // yields nothing
+        self.cells.chunks(self.width.max(1))
+    }
+
+    pub fn column(&self, x: usize) -> impl Iterator<Item = &T> {
+        self.cells.iter().skip(x).step_by(self.width.max(1))
+    }
+
+    pub fn columns(&self) -> impl Iterator<Item = impl Iterator<Item = &T>> {
+        (0..self.width).map(|x| self.column(x))
+    }
+}
+
+impl<T: Clone> Grid<T> {
+    pub fn filled(width: usize, height: usize, value: T) -> Self {
+        Self::new(width, height, vec![value; width * height])
+    }
+
+    /// Swap rows and columns
+    pub fn transpose(&self) -> Self {
+        let cells = self.columns().flat_map(|col| col.cloned()).collect();
+        Self::new(self.height, self.width, cells)
+    }
+
+    /// Rotate a quarter turn so that the left column becomes the top row
+    pub fn rotate_clockwise(&self) -> Self {
+        let cells = self
+            .columns()
+            .flat_map(|col| col.cloned().collect::<Vec<T>>().into_iter().rev())
+            .collect();
+        Self::new(self.height, self.width, cells)
+    }
+
+    /// Rotate a quarter turn so that the right column becomes the top row
+    pub fn rotate_counter_clockwise(&self) -> Self {
+        let cells = (0..self.width)
+            .rev()
+            .flat_map(|x| self.column(x).cloned())
+            .collect();
+        Self::new(self.height, self.width, cells)
+    }
+}
+
+impl<T> Index<Point> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, p: Point) -> &T {
+        self.get(p)
+            .unwrap_or_else(|| panic!("{:?} is outside of a {}x{} grid", p, self.width, self.height))
+    }
+}
+
+impl<T> IndexMut<Point> for Grid<T> {
+    fn index_mut(&mut self, p: Point) -> &mut T {
+        let (width, height) = (self.width, self.height);
+        self.get_mut(p)
+            .unwrap_or_else(|| panic!("{:?} is outside of a {}x{} grid", p, width, height))
+    }
+}
+
+impl Display for Grid<char> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for row in self.rows() {
+            writeln!(f, "{}", String::from_iter(row))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeSet;
+
+    /// A grid wider than it is tall, so that mixing up rows and columns shows
+    fn grid() -> Grid<char> {
+        Grid::from_rows(["abcd", "efgh", "ijkl"].iter().map(|row| row.chars().collect()).collect()).unwrap()
+    }
+
+    fn points(points: impl Iterator<Item = Point>) -> BTreeSet<(usize, usize)> {
+        points.map(|p| (p.x, p.y)).collect()
+    }
+
+    #[test]
+    fn from_rows_reports_the_first_ragged_row() {
+        let rows = vec![vec![1, 2], vec![3, 4], vec![5], vec![6]];
+        assert_eq!(Grid::from_rows(rows), Err(2));
+        assert_eq!(Grid::from_rows(vec![vec![1], vec![2, 3]]), Err(1));
+
+        let empty: Grid<u8> = Grid::from_rows(vec![]).unwrap();
+        assert_eq!((empty.width(), empty.height()), (0, 0));
+    }
+
+    #[test]
+    fn transpose() {
+        let transposed = grid().transpose();
+        assert_eq!((transposed.width(), transposed.height()), (3, 4));
+        assert_eq!(transposed.to_string(), "aei\nbfj\ncgk\ndhl\n");
+        assert_eq!(transposed.transpose(), grid());
+    }
+
+    #[test]
+    fn rotate_clockwise() {
+        let rotated = grid().rotate_clockwise();
+        assert_eq!((rotated.width(), rotated.height()), (3, 4));
+        assert_eq!(rotated.to_string(), "iea\njfb\nkgc\nlhd\n");
+    }
+
+    #[test]
+    fn rotate_counter_clockwise() {
+        let rotated = grid().rotate_counter_clockwise();
+        assert_eq!((rotated.width(), rotated.height()), (3, 4));
+        assert_eq!(rotated.to_string(), "dhl\ncgk\nbfj\naei\n");
+        assert_eq!(rotated.rotate_clockwise(), grid());
+    }
+
+    #[test]
+    fn four_rotations_are_the_same_grid() {
+        let rotated = grid()
+            .rotate_clockwise()
+            .rotate_clockwise()
+            .rotate_clockwise()
+            .rotate_clockwise();
+        assert_eq!(rotated, grid());
+    }
+
+    #[test]
+    fn neighbors_in_the_corners() {
+        let grid = grid();
+        assert_eq!(points(grid.neighbors4(Point::new(0, 0))), BTreeSet::from([(1, 0), (0, 1)]));
+        assert_eq!(points(grid.neighbors8(Point::new(0, 0))), BTreeSet::from([(1, 0), (0, 1), (1, 1)]));
+        assert_eq!(points(grid.neighbors4(Point::new(3, 2))), BTreeSet::from([(3, 1), (2, 2)]));
+        assert_eq!(points(grid.neighbors8(Point::new(3, 2))), BTreeSet::from([(3, 1), (2, 2), (2, 1)]));
+        assert_eq!(points(grid.neighbors8(Point::new(3, 0))), BTreeSet::from([(2, 0), (2, 1), (3, 1)]));
+    }
+
+    #[test]
+    fn neighbors_on_the_edges() {
+        let grid = grid();
+        assert_eq!(points(grid.neighbors4(Point::new(2, 0))), BTreeSet::from([(1, 0), (3, 0), (2, 1)]));
+        assert_eq!(
+            points(grid.neighbors8(Point::new(2, 0))),
+            BTreeSet::from([(1, 0), (3, 0), (1, 1), (2, 1), (3, 1)])
+        );
+        assert_eq!(points(grid.neighbors4(Point::new(0, 1))), BTreeSet::from([(0, 0), (1, 1), (0, 2)]));
+        assert_eq!(points(grid.neighbors8(Point::new(3, 1))).len(), 5);
+    }
+
+    #[test]
+    fn neighbors_inside() {
+        let grid = grid();
+        assert_eq!(points(grid.neighbors4(Point::new(1, 1))), BTreeSet::from([(1, 0), (2, 1), (1, 2), (0, 1)]));
+        assert_eq!(points(grid.neighbors8(Point::new(2, 1))).len(), 8);
+    }
+}
@@ -0,0 +1,249 @@
+use crate::error::{Result, WithPath};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Cursor, Read};
+use std::iter::Enumerate;
+use std::sync::OnceLock;
+
+/// The path that reads the puzzle input from stdin
+pub const STDIN: &str = "-";
+
+static STDIN_DATA: OnceLock<String> = OnceLock::new();
+
+/// Stdin can only be read once but each part reads its input separately, so the
+/// first read is kept for the rest of the run
+fn stdin() -> io::Result<&'static str> {
+    if let Some(data) = STDIN_DATA.get() {
+        return Ok(data);
+    }
+
+    let mut data = String::new();
+    io::stdin().read_to_string(&mut data)?;
+    Ok(STDIN_DATA.get_or_init(|| data))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Source {
+    File(String),
+    Stdin,
+    Str(String),
+}
+
+/// Where a puzzle input is read from. Every error about the input is reported
+/// against its [`name`](Input::name).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Input {
+    name: String,
+    source: Source,
+}
+
+impl Input {
+    pub fn file(path: &str) -> Self {
+        Self {
+            name: path.to_owned(),
+            source: Source::File(path.to_owned()),
+        }
+    }
+
+    pub fn stdin() -> Self {
+        Self {
+            name: "<stdin>".to_owned(),
+            source: Source::Stdin,
+        }
+    }
+
+    /// An input held in memory, such as an example from the puzzle text
+    pub fn text(name: &str, text: &str) -> Self {
+        Self {
+            name: name.to_owned(),
+            source: Source::Str(text.to_owned()),
+        }
+    }
+
+    /// The puzzle input for `day` at `inputs/day<N>/data.txt`
+    pub fn data(day: u32) -> Self {
+        Self::file(&format!("inputs/day{}/data.txt", day))
+    }
+
+    /// The example input for `day` at `inputs/day<N>/testdata.txt`
+    pub fn test(day: u32) -> Self {
+        Self::file(&format!("inputs/day{}/testdata.txt", day))
+    }
+
+    /// The input named on the command line, where `-` is stdin
+    pub fn from_arg(arg: &str) -> Self {
+        if arg == STDIN {
+            Self::stdin()
+        } else {
+            Self::file(arg)
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The file the input is read from, if it is read from one
+    pub fn path(&self) -> Option<&str> {
+        match &self.source {
+            Source::File(path) => Some(path),
+            Source::Stdin | Source::Str(_) => None,
+        }
+    }
+
+    pub fn open(&self) -> Result<Box<dyn BufRead + '_>> {
+        match &self.source {
+            Source::File(path) => Ok(Box::new(BufReader::new(
+                File::open(path).with_path(&self.name)?,
+            ))),
+            Source::Stdin => Ok(Box::new(Cursor::new(stdin().with_path(&self.name)?))),
+            Source::Str(text) => Ok(Box::new(Cursor::new(text.as_str()))),
+        }
+    }
+
+    pub fn read_to_string(&self) -> Result<String> {
+        let mut data = String::new();
+        self.open()?
+            .read_to_string(&mut data)
+            .with_path(&self.name)?;
+        Ok(data)
+    }
+
+    pub fn lines(&self) -> Result<Lines<'_>> {
+        Ok(Lines {
+            name: &self.name,
+            reader: self.open()?,
+        })
+    }
+
+    pub fn blocks(&self) -> Result<Blocks<'_>> {
+        Ok(Blocks {
+            lines: self.lines()?.enumerate(),
+        })
+    }
+}
+
+/// The lines of an input without their line endings
+pub struct Lines<'a> {
+    name: &'a str,
+    reader: Box<dyn BufRead + 'a>,
+}
+
+impl Iterator for Lines<'_> {
+    type Item = Result<String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut line = String::new();
+        match self.reader.read_line(&mut line).with_path(self.name) {
+            Ok(0) => None,
+            Ok(_) => {
+                let len = line.trim_end_matches(['\n', '\r']).len();
+                line.truncate(len);
+                Some(Ok(line))
+            }
+            Err(e) => Some(Err(e)),
+        }
+    }
+}
+
+/// A run of consecutive lines that aren't blank
+#[derive(Debug, Clone)]
+pub struct Block {
+    /// 0-based index of the first line of the block in the whole input
+    pub start: usize,
+    pub lines: Vec<String>,
+}
+
+impl Block {
+    /// Each line of the block along with its 0-based index in the whole input
+    pub fn numbered(&self) -> impl Iterator<Item = (usize, &str)> {
+        (self.start..).zip(self.lines.iter().map(String::as_str))
+    }
+}
+
+/// The blocks of an input, separated by one or more blank lines
+pub struct Blocks<'a> {
+    lines: Enumerate<Lines<'a>>,
+}
+
+impl Iterator for Blocks<'_> {
+    type Item = Result<Block>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut block = Block {
+            start: 0,
+            lines: vec![],
+        };
+
+        for (i, line) in self.lines.by_ref() {
+            let line = match line {
+                Ok(line) => line,
+                Err(e) => return Some(Err(e)),
+            };
+
+            if line.trim().is_empty() {
+                if block.lines.is_empty() {
+                    continue;
+                }
+                return Some(Ok(block));
+            }
+
+            if block.lines.is_empty() {
+                block.start = i;
+            }
+            block.lines.push(line);
+        }
+
+        (!block.lines.is_empty()).then_some(Ok(block))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn blocks(text: &str) -> Vec<(usize, Vec<String>)> {
+        Input::text("blocks", text)
+            .blocks()
+            .unwrap()
+            .map(|block| {
+                let block = block.unwrap();
+                (block.start, block.lines)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn blocks_skip_leading_repeated_and_trailing_blank_lines() {
+        let found = blocks("\n\na\nb\n\n\n  \nc\n\n\n");
+        assert_eq!(
+            found,
+            vec![
+                (2, vec!["a".to_owned(), "b".to_owned()]),
+                (7, vec!["c".to_owned()]),
+            ]
+        );
+    }
+
+    #[test]
+    fn blocks_of_blank_input() {
+        assert!(blocks("").is_empty());
+        assert!(blocks("\n\n").is_empty());
+    }
+
+    #[test]
+    fn block_without_trailing_newline() {
+        assert_eq!(blocks("a\r\n\r\nb"), vec![(0, vec!["a".to_owned()]), (2, vec!["b".to_owned()])]);
+    }
+
+    #[test]
+    fn stdin_is_read_once_and_kept() {
+        // nothing else in the tests reads stdin, so this fills the cache before it is
+        // ever read and the real stdin of the test run is left alone
+        STDIN_DATA.get_or_init(|| "1\n2\n".to_owned());
+
+        let input = Input::stdin();
+        assert_eq!(input.read_to_string().unwrap(), "1\n2\n");
+        let lines: Vec<String> = input.lines().unwrap().map(|l| l.unwrap()).collect();
+        assert_eq!(lines, vec!["1", "2"]);
+    }
+}
@@ -0,0 +1,17 @@
+//! Solutions to Advent of Code 2023. Every day implements [`days::solution::Solution`]
+//! and is listed in [`days::registry::DAYS`], the `aoc2023` binary is a command line
+//! front end over this library.
+#![feature(iter_map_windows)]
+
+pub mod bench;
+pub mod crosscheck;
+pub mod days;
+pub mod difftest;
+pub mod direction;
+pub mod error;
+pub mod generate;
+pub mod grid;
+pub mod input;
+pub mod log;
+pub mod runner;
+pub mod verify;
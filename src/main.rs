@@ -1,84 +1,311 @@
-#![feature(iter_map_windows)]
-#![feature(iter_next_chunk)]
-#![feature(slice_split_once)]
-use days::{
-    day1::Day1, day10::Day10, day11::Day11, day12::Day12, day13::Day13, day14::Day14, day15::Day15, day16::Day16, day2::Day2, day3::Day3, day4::Day4,
-    day5::Day5, day6::Day6, day7::Day7, day8::Day8, day9::Day9, solution::Solution,
-};
+use aoc2023::days::registry::{self, DAYS};
+use aoc2023::generate::Rng;
+use aoc2023::input::Input;
+use aoc2023::{bench, crosscheck, difftest, log, runner, verify};
+use aoc2023::days::day1::{self, Day1, Vocabulary};
+use aoc2023::days::day2::{self, Bag, Day2};
+use aoc2023::days::day3::{self, Day3};
+use aoc2023::days::day4::{self, Cascade, Day4};
+use aoc2023::days::solution::Solution;
+use cli::{BenchOptions, CascadeFormat, Command, Format, GameReport, InputChoice, Report, USAGE};
 use std::env::args;
+use std::ops::RangeInclusive;
+use std::process::ExitCode;
+use std::time::{SystemTime, UNIX_EPOCH};
 
-mod days;
-
-fn main() {
-    let mut args_list = args();
-    args_list.next();
-    let problem = args_list.next();
-    let file = args_list.next().expect("Missing file path");
-    match problem.as_deref() {
-        Some("1") => {
-            Day1::problem1(&file).unwrap();
-            Day1::problem2(&file).unwrap();
-        }
-        Some("2") => {
-            Day2::problem1(&file).unwrap();
-            Day2::problem2(&file).unwrap();
+mod cli;
+
+fn run(days: RangeInclusive<u32>, parts: &[usize], input: &InputChoice, format: Format) -> ExitCode {
+    let mut code = ExitCode::SUCCESS;
+    let single = days.start() == days.end();
+
+    for number in days {
+        let Some(day) = registry::get(number) else {
+            eprintln!("Day {} is not implemented", number);
+            code = ExitCode::FAILURE;
+            continue;
+        };
+        if !single && format == Format::Text {
+            println!("--- Day {}: {} ---", day.number, day.title);
         }
-        Some("3") => {
-            Day3::problem1(&file).unwrap();
-            Day3::problem2(&file).unwrap();
+
+        let input = input.input(day.number);
+        for &part in parts {
+            let outcome = runner::run_part(day, part, &input);
+            if outcome.result.is_err() {
+                code = ExitCode::FAILURE;
+            }
+
+            match (format, &outcome.result) {
+                (Format::Json, _) => println!("{}", outcome.to_json()),
+                (Format::Text, Ok(answer)) => println!("Day {} part {}: {}", day.number, part, answer),
+                (Format::Text, Err(e)) => eprintln!("Day {} part {} failed: {}", day.number, part, e),
+            }
         }
-        Some("4") => {
-            Day4::problem1(&file).unwrap();
-            Day4::problem2(&file).unwrap();
+    }
+
+    code
+}
+
+fn calibrate(part: usize, input: &InputChoice, vocabulary: Option<&str>, report: Report) -> ExitCode {
+    let vocabulary = match vocabulary {
+        Some(path) => Vocabulary::parse(&Input::from_arg(path)),
+        None if part == 1 => Ok(Vocabulary::digits()),
+        None => Ok(Vocabulary::english()),
+    };
+    let input = input.input(1);
+    let parsed = vocabulary.and_then(|vocabulary| Ok((vocabulary, Day1::parse(&input)?)));
+    let (vocabulary, lines) = match parsed {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            return ExitCode::FAILURE;
         }
-        Some("5") => {
-            Day5::problem1(&file).unwrap();
-            Day5::problem2(&file).unwrap();
+    };
+
+    let explained = vocabulary.explain(input.name(), &lines);
+    match report {
+        Report::Explain => day1::print_explained(&explained),
+        Report::Csv => print!("{}", day1::explained_csv(&explained)),
+        Report::Total => {}
+    }
+    if report != Report::Csv {
+        println!("Total: {}", explained.last().map_or(0, |e| e.total));
+    }
+
+    if explained.iter().all(|e| e.calibration.is_ok()) {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
+
+fn games(input: &InputChoice, bag: Option<&Input>, report: GameReport) -> ExitCode {
+    let input = input.input(2);
+    let bag = match bag.map_or(Ok(Bag::standard()), Bag::parse) {
+        Ok(bag) => bag,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            return ExitCode::FAILURE;
         }
-        Some("6") => {
-            Day6::problem1(&file).unwrap();
-            Day6::problem2(&file).unwrap();
+    };
+
+    let result = match report {
+        GameReport::Validate => day2::validate(&input, &bag).map(|problems| {
+            for problem in &problems {
+                println!("{}", problem);
+            }
+            println!("{} problems", problems.len());
+            problems.is_empty()
+        }),
+        GameReport::Games | GameReport::Stats => Day2::parse(&input).map(|games| {
+            match report {
+                GameReport::Stats => day2::print_stats(&games, &bag),
+                _ => day2::print_games(&games, &bag),
+            }
+            true
+        }),
+    };
+
+    match result {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            ExitCode::FAILURE
         }
-        Some("7") => {
-            Day7::problem1(&file).unwrap();
-            Day7::problem2(&file).unwrap();
+    }
+}
+
+fn run_bench(
+    days: RangeInclusive<u32>,
+    parts: &[usize],
+    input: &InputChoice,
+    options: &BenchOptions,
+) -> ExitCode {
+    let mut code = ExitCode::SUCCESS;
+    let mut benches = vec![];
+
+    runner::quietly(|| {
+        for day in days.filter_map(registry::get) {
+            let input = input.input(day.number);
+            for &part in parts {
+                match bench::bench_part(day, part, &input, options.runs) {
+                    Ok(result) => {
+                        bench::print_bench(&result);
+                        benches.push(result);
+                    }
+                    Err(e) => {
+                        eprintln!("Day {} part {} failed: {}", day.number, part, e);
+                        code = ExitCode::FAILURE;
+                    }
+                }
+            }
         }
-        Some("8") => {
-            Day8::problem1(&file).unwrap();
-            Day8::problem2(&file).unwrap();
+    });
+
+    if let Some(path) = &options.baseline {
+        let baseline = match bench::load(path) {
+            Ok(baseline) => baseline,
+            Err(e) => {
+                eprintln!("error: {}", e);
+                return ExitCode::FAILURE;
+            }
+        };
+
+        let regressions = bench::regressions(&benches, &baseline, options.threshold);
+        for r in &regressions {
+            println!(
+                "REGRESSION day {} part {} {}: {} -> {} (+{:.1}%)",
+                r.day,
+                r.part,
+                r.step,
+                runner::format_duration(r.before),
+                runner::format_duration(r.after),
+                r.slowdown(),
+            );
         }
-        Some("9") => {
-            Day9::problem1(&file).unwrap();
-            Day9::problem2(&file).unwrap();
+        if !regressions.is_empty() {
+            code = ExitCode::FAILURE;
         }
-        Some("10") => {
-            Day10::problem1(&file).unwrap();
-            Day10::problem2(&file).unwrap();
+    }
+
+    if let Some(path) = &options.save {
+        if let Err(e) = bench::save(path, &benches) {
+            eprintln!("error: {}", e);
+            return ExitCode::FAILURE;
         }
-        Some("11") => {
-            Day11::problem1(&file).unwrap();
-            Day11::problem2(&file).unwrap();
+    }
+
+    code
+}
+
+/// A seed from the clock, printed to stderr so that whatever it makes can be made
+/// again
+fn pick_seed() -> u64 {
+    let seed = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_nanos() as u64);
+    eprintln!("seed: {}", seed);
+    seed
+}
+
+fn main() -> ExitCode {
+    let (verbosity, args) = cli::take_verbosity(args().skip(1));
+    log::init(verbosity);
+
+    let command = match cli::parse(args.into_iter()) {
+        Ok(command) => command,
+        Err(e) => {
+            eprintln!("error: {}\n\n{}", e, USAGE);
+            return ExitCode::from(2);
         }
-        Some("12") => {
-            Day12::problem1(&file).unwrap();
-            Day12::problem2(&file).unwrap();
+    };
+
+    match command {
+        Command::Help => println!("{}", USAGE),
+        Command::All(Format::Text) => runner::print_table(&runner::run_all()),
+        Command::All(Format::Json) => runner::print_json(&runner::run_all()),
+        Command::Verify(days) => {
+            if !runner::quietly(|| verify::verify(days)) {
+                return ExitCode::FAILURE;
+            }
         }
-        Some("13") => {
-            Day13::problem1(&file).unwrap();
-            Day13::problem2(&file).unwrap();
+        Command::List => {
+            for day in DAYS {
+                println!("{:>2}: {}", day.number, day.title);
+            }
         }
-        Some("14") => {
-            Day14::problem1(&file).unwrap();
-            Day14::problem2(&file).unwrap();
+        Command::Run {
+            days,
+            parts,
+            input,
+            format,
+        } => return run(days, &parts, &input, format),
+        Command::Calibrate {
+            part,
+            input,
+            vocabulary,
+            report,
+        } => return calibrate(part, &input, vocabulary.as_deref(), report),
+        Command::Schematic { input, query } => match Day3::parse(&input.input(3)) {
+            Ok(schematic) => day3::print_query(&schematic, query),
+            Err(e) => {
+                eprintln!("error: {}", e);
+                return ExitCode::FAILURE;
+            }
+        },
+        Command::Gears { input, rules } => match Day3::parse(&input.input(3)) {
+            Ok(schematic) => day3::print_gears(&schematic, &rules),
+            Err(e) => {
+                eprintln!("error: {}", e);
+                return ExitCode::FAILURE;
+            }
         },
-        Some("15") => {
-            Day15::problem1(&file).unwrap();
-            Day15::problem2(&file).unwrap();
+        Command::Scores { input, scoring } => match Day4::parse(&input.input(4)) {
+            Ok(cards) => day4::print_scores(&cards, &scoring),
+            Err(e) => {
+                eprintln!("error: {}", e);
+                return ExitCode::FAILURE;
+            }
         },
-        Some("16") => {
-            Day16::problem1(&file).unwrap();
-            Day16::problem2(&file).unwrap();
+        Command::Cascade {
+            input,
+            format,
+            scoring,
+        } => match Day4::parse(&input.input(4)) {
+            Ok(cards) => {
+                let cascade = Cascade::simulate(&cards, &scoring);
+                match format {
+                    CascadeFormat::Text => day4::print_cascade(&cascade),
+                    CascadeFormat::Csv => print!("{}", cascade.to_csv()),
+                    CascadeFormat::Dot => print!("{}", cascade.to_dot()),
+                }
+            }
+            Err(e) => {
+                eprintln!("error: {}", e);
+                return ExitCode::FAILURE;
+            }
+        },
+        Command::Games { input, bag, report } => return games(&input, bag.as_ref(), report),
+        Command::Generate { day, size, seed } => {
+            let Some(day) = registry::get(day) else {
+                eprintln!("Day {} is not implemented", day);
+                return ExitCode::FAILURE;
+            };
+            let seed = seed.unwrap_or_else(pick_seed);
+            print!("{}", (day.generate)(&mut Rng::new(seed), size));
+        }
+        Command::Difftest {
+            days,
+            path,
+            runs,
+            size,
+            seed,
+        } => {
+            let input = path.as_deref().map(Input::from_arg);
+            let seed = match input {
+                Some(_) => 0,
+                None => seed.unwrap_or_else(pick_seed),
+            };
+            let agreed = runner::quietly(|| difftest::difftest(days, input.as_ref(), seed, runs, size));
+            if !agreed {
+                return ExitCode::FAILURE;
+            }
         }
-        _ => eprint!("usage: cargo run -- <problem_number>"),
+        Command::Crosscheck { days, parts, input } => {
+            let agreed = runner::quietly(|| crosscheck::crosscheck(days, &parts, |day| input.input(day)));
+            if !agreed {
+                return ExitCode::FAILURE;
+            }
+        }
+        Command::Bench {
+            days,
+            parts,
+            input,
+            options,
+        } => return run_bench(days, &parts, &input, &options),
     }
+
+    ExitCode::SUCCESS
 }
@@ -0,0 +1,173 @@
+use crate::days::answer::Answer;
+use crate::days::registry::{Day, Part, DAYS};
+use crate::input::Input;
+use serde::Serialize;
+use std::panic::{self, AssertUnwindSafe};
+use std::time::{Duration, Instant};
+
+/// The result of running a single part of a day
+pub struct Outcome {
+    pub day: u32,
+    pub part: usize,
+    /// The name of the input the part was run against
+    pub input: String,
+    pub result: Result<Answer, String>,
+    pub elapsed: Duration,
+}
+
+/// How an outcome is written in the JSON output format. Exactly one of `answer`
+/// and `error` is set and `duration` is in seconds.
+#[derive(Serialize)]
+struct Record<'a> {
+    day: u32,
+    part: usize,
+    answer: Option<&'a Answer>,
+    duration: f64,
+    input: &'a str,
+    error: Option<&'a str>,
+}
+
+impl Outcome {
+    /// The outcome as a single line JSON object
+    pub fn to_json(&self) -> String {
+        let record = Record {
+            day: self.day,
+            part: self.part,
+            answer: self.result.as_ref().ok(),
+            duration: self.elapsed.as_secs_f64(),
+            input: &self.input,
+            error: self.result.as_ref().err().map(String::as_str),
+        };
+        serde_json::to_string(&record).expect("outcomes always serialize")
+    }
+}
+
+fn panic_message(payload: Box<dyn std::any::Any + Send>) -> String {
+    if let Some(s) = payload.downcast_ref::<&str>() {
+        format!("panicked: {}", s)
+    } else if let Some(s) = payload.downcast_ref::<String>() {
+        format!("panicked: {}", s)
+    } else {
+        "panicked".to_owned()
+    }
+}
+
+/// Run `f`, turning both the error it returns and a panic into an error message
+pub fn catch<T>(f: impl FnOnce() -> crate::error::Result<T>) -> Result<T, String> {
+    match panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(value)) => Ok(value),
+        Ok(Err(e)) => Err(e.to_string()),
+        Err(payload) => Err(panic_message(payload)),
+    }
+}
+
+/// Run one part against `input`, turning both errors and panics into a failed outcome
+/// so that a single broken part cannot take down a whole run.
+pub fn run_part(day: &Day, part: usize, input: &Input) -> Outcome {
+    let solve: Part = day.parts[part - 1];
+    let start = Instant::now();
+    let result = catch(|| solve(input));
+
+    Outcome {
+        day: day.number,
+        part,
+        input: input.name().to_owned(),
+        result,
+        elapsed: start.elapsed(),
+    }
+}
+
+pub fn format_duration(d: Duration) -> String {
+    let micros = d.as_micros();
+    if micros < 1_000 {
+        format!("{}µs", micros)
+    } else if micros < 1_000_000 {
+        format!("{:.2}ms", micros as f64 / 1_000.0)
+    } else {
+        format!("{:.2}s", d.as_secs_f64())
+    }
+}
+
+/// Print every outcome as a JSON object on its own line
+pub fn print_json(outcomes: &[Outcome]) {
+    for outcome in outcomes {
+        println!("{}", outcome.to_json());
+    }
+}
+
+pub fn print_table(outcomes: &[Outcome]) {
+    let rows: Vec<[String; 4]> = outcomes
+        .iter()
+        .map(|o| {
+            let answer = match &o.result {
+                Ok(answer) => answer.to_string(),
+                Err(e) => format!("error: {}", e),
+            };
+            [
+                o.day.to_string(),
+                o.part.to_string(),
+                answer,
+                format_duration(o.elapsed),
+            ]
+        })
+        .collect();
+
+    let header = ["Day", "Part", "Answer", "Time"];
+    let mut widths = header.map(str::len);
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let print_row = |cells: [&str; 4]| {
+        println!(
+            "{:>w0$} | {:>w1$} | {:<w2$} | {:>w3$}",
+            cells[0],
+            cells[1],
+            cells[2],
+            cells[3],
+            w0 = widths[0],
+            w1 = widths[1],
+            w2 = widths[2],
+            w3 = widths[3],
+        );
+    };
+
+    print_row(header);
+    println!(
+        "{}",
+        widths
+            .iter()
+            .map(|w| "-".repeat(*w))
+            .collect::<Vec<String>>()
+            .join("-+-")
+    );
+    for row in &rows {
+        print_row([&row[0], &row[1], &row[2], &row[3]]);
+    }
+}
+
+/// Run `f` with the default panic hook silenced. Used when panics are caught and
+/// reported as part of the output so their messages don't get printed over it.
+pub fn quietly<T>(f: impl FnOnce() -> T) -> T {
+    let hook = panic::take_hook();
+    panic::set_hook(Box::new(|_| {}));
+    let result = f();
+    panic::set_hook(hook);
+    result
+}
+
+/// Run both parts of every registered day against its `inputs/dayN/data.txt`
+pub fn run_all() -> Vec<Outcome> {
+    quietly(|| {
+        let mut outcomes = vec![];
+        for day in DAYS {
+            let input = Input::data(day.number);
+            for part in 1..=day.parts.len() {
+                outcomes.push(run_part(day, part, &input));
+            }
+        }
+        outcomes
+    })
+}
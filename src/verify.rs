@@ -0,0 +1,130 @@
+use crate::days::registry::{self, Day};
+use crate::input::Input;
+use crate::runner;
+use std::fs;
+use std::io::{Error, ErrorKind, Result};
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
+
+/// Answers recorded for an input file in a sidecar `<name>.expected` file. The file
+/// holds one `part<N>: <answer>` line per known part, parts without a line are not
+/// checked. Blank lines and lines starting with `#` are ignored.
+#[derive(Debug, Default)]
+pub struct Expected {
+    parts: [Option<String>; 2],
+}
+
+impl Expected {
+    pub fn parse(text: &str) -> std::result::Result<Self, String> {
+        let mut expected = Self::default();
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let part = line
+                .split_once(':')
+                .and_then(|(part, answer)| Some((part.trim().strip_prefix("part")?, answer)))
+                .and_then(|(part, answer)| Some((part.parse::<usize>().ok()?, answer.trim())));
+            match part {
+                Some((n @ 1..=2, answer)) => expected.parts[n - 1] = Some(answer.to_owned()),
+                _ => return Err(format!("line {}: expected `part<1|2>: <answer>`", i + 1)),
+            }
+        }
+
+        Ok(expected)
+    }
+
+    pub fn read(path: &Path) -> Result<Self> {
+        let text = fs::read_to_string(path)?;
+        Self::parse(&text).map_err(|e| {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!("{}: {}", path.display(), e),
+            )
+        })
+    }
+
+    pub fn part(&self, part: usize) -> Option<&str> {
+        self.parts[part - 1].as_deref()
+    }
+}
+
+/// Every input under `inputs/day<N>` that has a sidecar file with expectations
+fn expectation_files(day: &Day) -> Result<Vec<(PathBuf, PathBuf)>> {
+    let dir = PathBuf::from(format!("inputs/day{}", day.number));
+    if !dir.is_dir() {
+        return Ok(vec![]);
+    }
+
+    let mut files = vec![];
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension().is_some_and(|ext| ext == "expected") {
+            files.push((path.with_extension("txt"), path));
+        }
+    }
+    files.sort();
+
+    Ok(files)
+}
+
+/// Check every registered day in `days` against its recorded answers, printing a
+/// line per check. Returns whether all checks passed, which they don't when there
+/// was nothing to check.
+pub fn verify(days: RangeInclusive<u32>) -> bool {
+    let (first, last) = (*days.start(), *days.end());
+    let mut passed = 0;
+    let mut failed = 0;
+
+    for day in days.filter_map(registry::get) {
+        let files = match expectation_files(day) {
+            Ok(files) => files,
+            Err(e) => {
+                println!("FAIL day {}: could not list inputs: {}", day.number, e);
+                failed += 1;
+                continue;
+            }
+        };
+
+        for (input, sidecar) in files {
+            let expected = match Expected::read(&sidecar) {
+                Ok(expected) => expected,
+                Err(e) => {
+                    println!("FAIL {}: {}", sidecar.display(), e);
+                    failed += 1;
+                    continue;
+                }
+            };
+
+            for part in 1..=day.parts.len() {
+                let Some(want) = expected.part(part) else {
+                    continue;
+                };
+                let outcome = runner::run_part(day, part, &Input::file(&input.to_string_lossy()));
+                let got = match outcome.result {
+                    Ok(answer) => answer.to_string(),
+                    Err(e) => format!("error: {}", e),
+                };
+
+                if got == want {
+                    println!("PASS {} part {}: {}", input.display(), part, got);
+                    passed += 1;
+                } else {
+                    println!("FAIL {} part {}", input.display(), part);
+                    println!("    - {}", want);
+                    println!("    + {}", got);
+                    failed += 1;
+                }
+            }
+        }
+    }
+
+    println!("{} passed, {} failed", passed, failed);
+    if passed + failed == 0 {
+        println!("FAIL no recorded answers for days {}..={}", first, last);
+        return false;
+    }
+    failed == 0
+}